@@ -1,3 +1,21 @@
+use std::path::PathBuf;
+
 fn main() {
-    prost_build::compile_protos(&["proto/krec.proto"], &["proto/"]).unwrap();
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    prost_build::Config::new()
+        .file_descriptor_set_path(out_dir.join("krec_descriptor.bin"))
+        .compile_protos(&["proto/krec.proto"], &["proto/"])
+        .unwrap();
+
+    // Generate serde impls following the protobuf JSON mapping
+    #[cfg(feature = "serde")]
+    {
+        let descriptor_set = std::fs::read(out_dir.join("krec_descriptor.bin")).unwrap();
+        pbjson_build::Builder::new()
+            .register_descriptors(&descriptor_set)
+            .unwrap()
+            .preserve_proto_field_names()
+            .build(&[".krec.proto"])
+            .unwrap();
+    }
 }
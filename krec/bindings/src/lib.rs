@@ -1,10 +1,17 @@
+use arrow::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
+use arrow::record_batch::{RecordBatch, RecordBatchIterator};
 use krec::{
-    ActuatorCommand, ActuatorConfig, ActuatorState, ImuQuaternion, ImuValues, KRec, KRecFrame,
-    KRecHeader, Vec3,
+    ActuatorCommand, ActuatorConfig, ActuatorField, ActuatorState, ActuatorTracking, AngularField,
+    AngularUnit, Axis, Catalog, CatalogEntry, FieldStats, FrameArrays, ImuField, ImuQuaternion,
+    ImuValues, KRec, KRecFrame, KRecHeader, KRecStats, LeRobotOptions, QuaternionComponent,
+    ResampleMethod, ShardWriter, ShardedDataset, Smoothing, TableLayout, TrackingError,
+    TrackingOptions, UnitSystem, Vec3, Vec3Stats,
 };
+use numpy::ndarray::{Array1, Array2, Array3};
+use numpy::{AllowTypeChange, IntoPyArray, PyArrayLike1, PyArrayLike2, PyArrayLike3};
 use pyo3::exceptions::{PyIndexError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyIterator;
+use pyo3::types::{PyDict, PyIterator};
 use pyo3_stub_gen::define_stub_info_gatherer;
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyfunction, gen_stub_pymethods};
 use tracing::{debug, info, instrument, warn};
@@ -748,6 +755,11 @@ impl PyKRec {
         self.inner.frames.len()
     }
 
+    /// SHA-256 of the canonical frame encoding as a hex string; the header is not included
+    fn content_hash(&self) -> String {
+        self.inner.content_hash()
+    }
+
     /// Get a frame by index (Python [] operator)
     fn __getitem__(&self, index: isize) -> PyResult<PyKRecFrame> {
         let len = self.inner.frames.len() as isize;
@@ -824,6 +836,17 @@ impl PyKRec {
             "End Timestamp: {}\n",
             self.inner.header.end_timestamp
         ));
+        let units: Vec<String> = AngularField::ALL
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}={}",
+                    field.name(),
+                    self.inner.header.angular_unit(*field)
+                )
+            })
+            .collect();
+        output.push_str(&format!("Angular Units: {}\n", units.join(", ")));
 
         // Actuator configs
         output.push_str(&format!(
@@ -852,32 +875,130 @@ impl PyKRec {
                 "Time range: {} to {}\n",
                 first_frame.video_timestamp, last_frame.video_timestamp
             ));
-
-            // Sample frame details (first frame)
-            output.push_str("\nFirst frame details:\n");
-            output.push_str(&format!(
-                "  Video frame number: {}\n",
-                first_frame.video_frame_number
-            ));
-            output.push_str(&format!(
-                "  Inference step: {}\n",
-                first_frame.inference_step
-            ));
-            output.push_str(&format!(
-                "  Actuator states: {}\n",
-                first_frame.actuator_states.len()
-            ));
-            if !first_frame.actuator_commands.is_empty() {
-                output.push_str("  Has actuator commands: yes\n");
-            }
-            if first_frame.imu_values.is_some() {
-                output.push_str("  Has IMU values: yes\n");
-            }
         }
 
+        // Statistics
+        output.push_str("\nStatistics\n");
+        output.push_str("----------\n");
+        output.push_str(&self.inner.stats().to_string());
+
         output
     }
 
+    /// Compute summary statistics (frame timing, gaps, per-actuator ranges, IMU ranges)
+    fn stats(&self, py: Python<'_>) -> PyResult<PyObject> {
+        Ok(stats_to_dict(py, &self.inner.stats())?.into_any().unbind())
+    }
+
+    /// Compare actuator commands against measured states, per actuator.
+    ///
+    /// The command in frame N is compared against the state in frame N + lag_frames, and the
+    /// latency is estimated by cross-correlating commanded and measured positions.
+    #[pyo3(signature = (lag_frames=0, max_lag_frames=50))]
+    fn tracking_error(
+        &self,
+        py: Python<'_>,
+        lag_frames: usize,
+        max_lag_frames: usize,
+    ) -> PyResult<Vec<PyObject>> {
+        let options = TrackingOptions {
+            lag_frames,
+            max_lag_frames,
+        };
+        self.inner
+            .tracking_error(&options)
+            .iter()
+            .map(|tracking| Ok(tracking_to_dict(py, tracking)?.into_any().unbind()))
+            .collect()
+    }
+
+    /// Resample onto a uniform real_timestamp grid
+    ///
+    /// method is one of "linear" (SLERP for quaternions), "nearest" or "hold". Online flags
+    /// and commands always come from the nearest (or, for "hold", the previous) frame.
+    #[pyo3(signature = (rate_hz, method="linear"))]
+    fn resample(&self, rate_hz: f64, method: &str) -> PyResult<PyKRec> {
+        let method = method
+            .parse::<ResampleMethod>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let krec = self
+            .inner
+            .resample(rate_hz, method)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyKRec { inner: krec })
+    }
+
+    /// Compute velocity, acceleration and jerk per actuator from positions and timestamps
+    ///
+    /// smoothing is one of "none", "savgol" (uses window and order) or "lowpass" (uses
+    /// cutoff_hz). Returns a dict mapping actuator ID to per-frame lists, with None where the
+    /// actuator is offline or a derivative is unavailable.
+    #[pyo3(signature = (smoothing="none", window=7, order=2, cutoff_hz=None))]
+    fn derived_signals(
+        &self,
+        py: Python<'_>,
+        smoothing: &str,
+        window: usize,
+        order: usize,
+        cutoff_hz: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let smoothing = parse_smoothing(smoothing, window, order, cutoff_hz)?;
+        let derived = self
+            .inner
+            .derived_signals(&smoothing)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let dict = PyDict::new_bound(py);
+        for signals in derived {
+            let signals_dict = PyDict::new_bound(py);
+            signals_dict.set_item("velocity", signals.velocity)?;
+            signals_dict.set_item("acceleration", signals.acceleration)?;
+            signals_dict.set_item("jerk", signals.jerk)?;
+            dict.set_item(signals.actuator_id, signals_dict)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Fill missing actuator velocities from differentiated positions, returning the count filled
+    #[pyo3(signature = (smoothing="none", window=7, order=2, cutoff_hz=None))]
+    fn fill_missing_velocity(
+        &mut self,
+        smoothing: &str,
+        window: usize,
+        order: usize,
+        cutoff_hz: Option<f64>,
+    ) -> PyResult<usize> {
+        let smoothing = parse_smoothing(smoothing, window, order, cutoff_hz)?;
+        self.inner
+            .fill_missing_velocity(&smoothing)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Convert angular states, commands and gyro values to the given units, updating the header
+    #[pyo3(signature = (angular="radians", state_position=None, state_velocity=None, command_position=None, command_velocity=None, imu_gyro=None))]
+    fn convert_units(
+        &mut self,
+        angular: &str,
+        state_position: Option<&str>,
+        state_velocity: Option<&str>,
+        command_position: Option<&str>,
+        command_velocity: Option<&str>,
+        imu_gyro: Option<&str>,
+    ) -> PyResult<()> {
+        let target = parse_unit_system(
+            angular,
+            [
+                state_position,
+                state_velocity,
+                command_position,
+                command_velocity,
+                imu_gyro,
+            ],
+        )?;
+        self.inner.convert_units(&target);
+        Ok(())
+    }
+
     /// Returns a more detailed string representation of a specific frame
     fn display_frame(&self, frame_number: usize) -> PyResult<String> {
         if frame_number >= self.inner.frames.len() {
@@ -980,6 +1101,317 @@ impl PyKRec {
         Ok(Self { inner: krec })
     }
 
+    /// Read only the header of a .krec file, without reading any frames
+    #[staticmethod]
+    fn read_header(path: &str) -> PyResult<PyKRecHeader> {
+        let header = KRec::read_header(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(PyKRecHeader { inner: header })
+    }
+
+    /// Count the frames of a .krec file from their length prefixes, without decoding them
+    #[staticmethod]
+    fn read_frame_count(path: &str) -> PyResult<usize> {
+        KRec::frame_count(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    /// Actuator IDs in the default array order: header configs first, then others by ID
+    fn actuator_ids(&self) -> Vec<u32> {
+        self.inner.actuator_ids()
+    }
+
+    /// Frame `real_timestamp`s as a uint64 NumPy array
+    fn timestamps(&self, py: Python<'_>) -> PyObject {
+        Array1::from(self.inner.timestamps())
+            .into_pyarray_bound(py)
+            .into_any()
+            .unbind()
+    }
+
+    /// Actuator positions as a (frames, actuators) float64 array, NaN where missing
+    #[pyo3(signature = (actuator_ids=None))]
+    fn positions(&self, py: Python<'_>, actuator_ids: Option<Vec<u32>>) -> PyResult<PyObject> {
+        self.actuator_array(py, ActuatorField::Position, actuator_ids)
+    }
+
+    /// Actuator velocities as a (frames, actuators) float64 array, NaN where missing
+    #[pyo3(signature = (actuator_ids=None))]
+    fn velocities(&self, py: Python<'_>, actuator_ids: Option<Vec<u32>>) -> PyResult<PyObject> {
+        self.actuator_array(py, ActuatorField::Velocity, actuator_ids)
+    }
+
+    /// Actuator torques as a (frames, actuators) float64 array, NaN where missing
+    #[pyo3(signature = (actuator_ids=None))]
+    fn torques(&self, py: Python<'_>, actuator_ids: Option<Vec<u32>>) -> PyResult<PyObject> {
+        self.actuator_array(py, ActuatorField::Torque, actuator_ids)
+    }
+
+    /// Actuator commands as a (frames, actuators, 3) float64 array of [position, velocity,
+    /// torque], NaN where missing
+    #[pyo3(signature = (actuator_ids=None))]
+    fn commands(&self, py: Python<'_>, actuator_ids: Option<Vec<u32>>) -> PyResult<PyObject> {
+        let ids = self.resolve_actuator_ids(actuator_ids)?;
+        let fields = [
+            ActuatorField::CommandPosition,
+            ActuatorField::CommandVelocity,
+            ActuatorField::CommandTorque,
+        ]
+        .map(|field| self.inner.actuator_array(field, &ids));
+        let data: Vec<f64> = (0..fields[0].len())
+            .flat_map(|i| fields.iter().map(move |values| values[i]))
+            .collect();
+        let array = Array3::from_shape_vec((self.inner.frames.len(), ids.len(), 3), data)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(array.into_pyarray_bound(py).into_any().unbind())
+    }
+
+    /// IMU accelerometer values as a (frames, 3) float64 array, NaN where missing
+    fn imu_accel(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.imu_array(py, &Axis::ALL.map(ImuField::Accel))
+    }
+
+    /// IMU gyroscope values as a (frames, 3) float64 array, NaN where missing
+    fn imu_gyro(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.imu_array(py, &Axis::ALL.map(ImuField::Gyro))
+    }
+
+    /// IMU orientation as a (frames, 4) float64 array of [x, y, z, w], NaN where missing
+    fn imu_quaternion(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.imu_array(py, &QuaternionComponent::ALL.map(ImuField::Quaternion))
+    }
+
+    /// All dense arrays in a dict keyed by name, using the given actuator ordering
+    #[pyo3(signature = (actuator_ids=None))]
+    fn to_numpy(&self, py: Python<'_>, actuator_ids: Option<Vec<u32>>) -> PyResult<PyObject> {
+        let ids = self.resolve_actuator_ids(actuator_ids)?;
+        let dict = PyDict::new_bound(py);
+        dict.set_item("timestamps", self.timestamps(py))?;
+        dict.set_item("actuator_ids", ids.clone())?;
+        dict.set_item("positions", self.positions(py, Some(ids.clone()))?)?;
+        dict.set_item("velocities", self.velocities(py, Some(ids.clone()))?)?;
+        dict.set_item("torques", self.torques(py, Some(ids.clone()))?)?;
+        dict.set_item("commands", self.commands(py, Some(ids))?)?;
+        dict.set_item("imu_accel", self.imu_accel(py)?)?;
+        dict.set_item("imu_gyro", self.imu_gyro(py)?)?;
+        dict.set_item("imu_quaternion", self.imu_quaternion(py)?)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Build a KRec from dense arrays in one call, with NaN for missing values.
+    ///
+    /// Actuator columns follow `header.actuator_configs`: positions, velocities and torques are
+    /// (frames, actuators), commands is (frames, actuators, 3) as [position, velocity, torque],
+    /// and imu is (frames, 3 | 6 | 9 | 13) as accel, gyro, mag and quaternion [x, y, z, w].
+    #[staticmethod]
+    #[pyo3(signature = (header, timestamps, positions=None, velocities=None, torques=None, commands=None, imu=None))]
+    fn from_arrays(
+        header: &PyKRecHeader,
+        timestamps: &Bound<'_, PyAny>,
+        positions: Option<&Bound<'_, PyAny>>,
+        velocities: Option<&Bound<'_, PyAny>>,
+        torques: Option<&Bound<'_, PyAny>>,
+        commands: Option<&Bound<'_, PyAny>>,
+        imu: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let timestamps: Vec<u64> = timestamps
+            .extract::<PyArrayLike1<u64, AllowTypeChange>>()?
+            .as_array()
+            .to_vec();
+        let frames = timestamps.len();
+        let actuators = header.inner.actuator_configs.len();
+
+        let positions = extract_matrix("positions", positions, frames, Some(actuators))?;
+        let velocities = extract_matrix("velocities", velocities, frames, Some(actuators))?;
+        let torques = extract_matrix("torques", torques, frames, Some(actuators))?;
+        let imu = extract_matrix("imu", imu, frames, None)?;
+        let commands = match commands {
+            Some(commands) => {
+                let commands = commands.extract::<PyArrayLike3<f64, AllowTypeChange>>()?;
+                let array = commands.as_array();
+                if array.dim() != (frames, actuators, 3) {
+                    return Err(PyValueError::new_err(format!(
+                        "commands has shape {:?}, expected ({}, {}, 3)",
+                        array.shape(),
+                        frames,
+                        actuators
+                    )));
+                }
+                Some(array.iter().copied().collect::<Vec<f64>>())
+            }
+            None => None,
+        };
+
+        let arrays = FrameArrays {
+            timestamps: &timestamps,
+            positions: positions.as_ref().map(|(data, _)| data.as_slice()),
+            velocities: velocities.as_ref().map(|(data, _)| data.as_slice()),
+            torques: torques.as_ref().map(|(data, _)| data.as_slice()),
+            commands: commands.as_deref(),
+            imu: imu.as_ref().map(|(data, _)| data.as_slice()),
+            imu_width: imu.as_ref().map(|(_, width)| *width).unwrap_or(0),
+        };
+        let krec = KRec::from_arrays(header.inner.clone(), &arrays)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self { inner: krec })
+    }
+
+    /// Save as a wide CSV table (one row per frame)
+    fn to_csv(&self, path: &str) -> PyResult<()> {
+        self.inner
+            .to_csv(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    /// Load a wide CSV table, using `header` to resolve named actuator columns
+    #[staticmethod]
+    fn from_csv(path: &str, header: &PyKRecHeader) -> PyResult<Self> {
+        let krec = KRec::from_csv(path, header.inner.clone())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(Self { inner: krec })
+    }
+
+    /// Serialize to JSON (`{"header": ..., "frames": [...]}`) or, with `ndjson=True`, to
+    /// newline-delimited JSON with a header line followed by one line per frame. Field names
+    /// follow `proto/krec.proto` (protobuf JSON mapping)
+    #[pyo3(signature = (ndjson=false))]
+    fn to_json(&self, ndjson: bool) -> PyResult<String> {
+        let mut buffer = Vec::new();
+        if ndjson {
+            self.inner.write_ndjson(&mut buffer)
+        } else {
+            self.inner.write_json(&mut buffer)
+        }
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        String::from_utf8(buffer).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Parse a recording from a string produced by `to_json`
+    #[staticmethod]
+    #[pyo3(signature = (text, ndjson=false))]
+    fn from_json(text: &str, ndjson: bool) -> PyResult<Self> {
+        let krec = if ndjson {
+            KRec::read_ndjson(text.as_bytes())
+        } else {
+            KRec::read_json(text.as_bytes())
+        }
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self { inner: krec })
+    }
+
+    /// Convert to a pyarrow.Table (layout "long": one row per frame and actuator, or "wide":
+    /// one row per frame), sharing the Arrow buffers via the C data interface
+    #[pyo3(signature = (layout="long"))]
+    fn to_arrow(&self, py: Python<'_>, layout: &str) -> PyResult<PyObject> {
+        let layout = parse_table_layout(layout)?;
+        let batch = self
+            .inner
+            .to_record_batch(layout)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let schema = batch.schema();
+        let mut stream =
+            FFI_ArrowArrayStream::new(Box::new(RecordBatchIterator::new([Ok(batch)], schema)));
+
+        // pyarrow takes ownership of the stream and resets its release callback
+        let reader = py
+            .import_bound("pyarrow")?
+            .getattr("RecordBatchReader")?
+            .call_method1(
+                "_import_from_c",
+                (&mut stream as *mut FFI_ArrowArrayStream as usize,),
+            )?;
+        Ok(reader.call_method0("read_all")?.unbind())
+    }
+
+    /// Convert to a pandas DataFrame (layout "wide" or "long"); the header is stored in
+    /// `df.attrs["krec_header"]` so it round-trips through `KRec.from_dataframe`
+    #[pyo3(signature = (layout="wide"))]
+    fn to_dataframe(&self, py: Python<'_>, layout: &str) -> PyResult<PyObject> {
+        let df = self.to_arrow(py, layout)?.call_method0(py, "to_pandas")?;
+        df.bind(py)
+            .getattr("attrs")?
+            .set_item("krec_header", self.inner.header.to_metadata())?;
+        Ok(df)
+    }
+
+    /// Build a KRec from a pandas DataFrame in either layout, using `header` or, if omitted,
+    /// the header stored in `df.attrs["krec_header"]`
+    #[staticmethod]
+    #[pyo3(signature = (df, header=None))]
+    fn from_dataframe(
+        py: Python<'_>,
+        df: &Bound<'_, PyAny>,
+        header: Option<&PyKRecHeader>,
+    ) -> PyResult<Self> {
+        let header = match header {
+            Some(header) => header.inner.clone(),
+            None => {
+                let metadata = df.getattr("attrs")?.call_method1("get", ("krec_header",))?;
+                if metadata.is_none() {
+                    return Err(PyValueError::new_err(
+                        "No header given and df.attrs has no 'krec_header'",
+                    ));
+                }
+                KRecHeader::from_metadata(&metadata.extract()?)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?
+            }
+        };
+
+        let kwargs = PyDict::new_bound(py);
+        kwargs.set_item("preserve_index", false)?;
+        let table = py.import_bound("pyarrow")?.getattr("Table")?.call_method(
+            "from_pandas",
+            (df,),
+            Some(&kwargs),
+        )?;
+
+        // pyarrow moves its stream into ours; the reader then owns and releases it
+        let mut stream = FFI_ArrowArrayStream::empty();
+        table.call_method0("to_reader")?.call_method1(
+            "_export_to_c",
+            (&mut stream as *mut FFI_ArrowArrayStream as usize,),
+        )?;
+        let batches = ArrowArrayStreamReader::try_new(stream)
+            .and_then(|reader| reader.collect::<Result<Vec<RecordBatch>, _>>())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let krec = KRec::from_record_batches(header, &batches)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self { inner: krec })
+    }
+
+    /// Save as a Parquet file with the header stored in the file metadata
+    #[pyo3(signature = (path, layout="long"))]
+    fn to_parquet(&self, path: &str, layout: &str) -> PyResult<()> {
+        let layout = parse_table_layout(layout)?;
+        self.inner
+            .to_parquet(path, layout)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    /// Load a Parquet file in either layout, reading the header from the file metadata
+    #[staticmethod]
+    fn from_parquet(path: &str) -> PyResult<Self> {
+        let krec = KRec::from_parquet(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(Self { inner: krec })
+    }
+
+    /// Save as an MCAP file (frames as protobuf messages, header as metadata)
+    fn to_mcap(&self, path: &str) -> PyResult<()> {
+        self.inner
+            .to_mcap(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    /// Load an MCAP file written by `to_mcap`
+    #[staticmethod]
+    fn from_mcap(path: &str) -> PyResult<Self> {
+        let krec = KRec::from_mcap(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(Self { inner: krec })
+    }
+
     fn combine_with_video(&self, video_path: &str, output_path: &str) -> PyResult<()> {
         // First save the KRec to a temporary file
         let temp_path = format!("{}.tmp.krec", output_path);
@@ -997,6 +1429,43 @@ impl PyKRec {
     }
 }
 
+impl PyKRec {
+    fn resolve_actuator_ids(&self, actuator_ids: Option<Vec<u32>>) -> PyResult<Vec<u32>> {
+        let Some(ids) = actuator_ids else {
+            return Ok(self.inner.actuator_ids());
+        };
+        for (i, id) in ids.iter().enumerate() {
+            if ids[..i].contains(id) {
+                return Err(PyValueError::new_err(format!(
+                    "Duplicate actuator ID {} in actuator_ids",
+                    id
+                )));
+            }
+        }
+        Ok(ids)
+    }
+
+    fn actuator_array(
+        &self,
+        py: Python<'_>,
+        field: ActuatorField,
+        actuator_ids: Option<Vec<u32>>,
+    ) -> PyResult<PyObject> {
+        let ids = self.resolve_actuator_ids(actuator_ids)?;
+        let data = self.inner.actuator_array(field, &ids);
+        let array = Array2::from_shape_vec((self.inner.frames.len(), ids.len()), data)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(array.into_pyarray_bound(py).into_any().unbind())
+    }
+
+    fn imu_array(&self, py: Python<'_>, fields: &[ImuField]) -> PyResult<PyObject> {
+        let data = self.inner.imu_array(fields);
+        let array = Array2::from_shape_vec((self.inner.frames.len(), fields.len()), data)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(array.into_pyarray_bound(py).into_any().unbind())
+    }
+}
+
 #[gen_stub_pyclass]
 #[pyclass(name = "KRecHeader")]
 #[derive(Debug, Clone)]
@@ -1137,6 +1606,39 @@ impl PyKRecHeader {
     fn clear_actuator_configs(&mut self) {
         self.inner.actuator_configs.clear();
     }
+
+    /// Get the effective angular unit of each field ("degrees" or "radians")
+    fn get_units(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new_bound(py);
+        for field in AngularField::ALL {
+            dict.set_item(field.name(), self.inner.angular_unit(field).to_string())?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Declare the angular units of the recorded values without converting them
+    #[pyo3(signature = (angular="degrees", state_position=None, state_velocity=None, command_position=None, command_velocity=None, imu_gyro=None))]
+    fn set_units(
+        &mut self,
+        angular: &str,
+        state_position: Option<&str>,
+        state_velocity: Option<&str>,
+        command_position: Option<&str>,
+        command_velocity: Option<&str>,
+        imu_gyro: Option<&str>,
+    ) -> PyResult<()> {
+        self.inner.units = Some(parse_unit_system(
+            angular,
+            [
+                state_position,
+                state_velocity,
+                command_position,
+                command_velocity,
+                imu_gyro,
+            ],
+        )?);
+        Ok(())
+    }
 }
 
 #[gen_stub_pyclass]
@@ -1314,6 +1816,197 @@ impl PyKRecFrame {
     }
 }
 
+/// SQLite catalog of recording headers
+#[gen_stub_pyclass]
+#[pyclass(name = "Catalog", unsendable)]
+struct PyCatalog {
+    inner: Catalog,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyCatalog {
+    /// Open the catalog database at `path`, creating it if needed
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        let inner = Catalog::open(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Index `.krec` files and combined videos under `dir`, returning counts of indexed,
+    /// unchanged, failed and removed files
+    fn index<'py>(&mut self, py: Python<'py>, dir: &str) -> PyResult<Bound<'py, PyDict>> {
+        let summary = self
+            .inner
+            .index_dir(dir)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        let dict = PyDict::new_bound(py);
+        dict.set_item("indexed", summary.indexed)?;
+        dict.set_item("unchanged", summary.unchanged)?;
+        dict.set_item("failed", summary.failed)?;
+        dict.set_item("removed", summary.removed)?;
+        Ok(dict)
+    }
+
+    /// Entries matching an SQL WHERE clause (all entries if empty), as a list of dicts
+    #[pyo3(signature = (where_clause=""))]
+    fn query<'py>(&self, py: Python<'py>, where_clause: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let entries = self
+            .inner
+            .query(where_clause)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        entries
+            .iter()
+            .map(|entry| catalog_entry_to_dict(py, entry))
+            .collect()
+    }
+
+    /// Paths of the entries matching an SQL WHERE clause
+    #[pyo3(signature = (where_clause=""))]
+    fn paths(&self, where_clause: &str) -> PyResult<Vec<String>> {
+        let entries = self
+            .inner
+            .query(where_clause)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(entries.into_iter().map(|entry| entry.path).collect())
+    }
+
+    /// Entries matching an SQL WHERE clause as a pandas DataFrame
+    #[pyo3(signature = (where_clause=""))]
+    fn to_pandas(&self, py: Python<'_>, where_clause: &str) -> PyResult<PyObject> {
+        let rows = self.query(py, where_clause)?;
+        let columns = [
+            "path",
+            "uuid",
+            "task",
+            "robot_platform",
+            "robot_serial",
+            "start_timestamp",
+            "end_timestamp",
+            "frame_count",
+            "actuator_ids",
+            "file_size",
+            "modified_ns",
+        ];
+        let kwargs = PyDict::new_bound(py);
+        kwargs.set_item("columns", columns.to_vec())?;
+        let df = py
+            .import_bound("pandas")?
+            .call_method("DataFrame", (rows,), Some(&kwargs))?;
+        Ok(df.unbind())
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        let count = self
+            .inner
+            .query("")
+            .map_err(|e| PyValueError::new_err(e.to_string()))?
+            .len();
+        Ok(format!("Catalog(recordings={})", count))
+    }
+}
+
+/// Random access over shards written by `write_shards`
+#[gen_stub_pyclass]
+#[pyclass(name = "ShardedDataset")]
+struct PyShardedDataset {
+    inner: ShardedDataset,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyShardedDataset {
+    /// Open every .tar shard in `dir`, reading only their indexes
+    #[new]
+    fn new(dir: &str) -> PyResult<Self> {
+        let inner = ShardedDataset::open(dir)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Load episode `index` as a KRec
+    fn __getitem__(&self, index: isize) -> PyResult<PyKRec> {
+        let len = self.inner.len() as isize;
+        let index = if index < 0 { index + len } else { index };
+        if index < 0 || index >= len {
+            return Err(PyIndexError::new_err("Episode index out of range"));
+        }
+        let krec = self
+            .inner
+            .load(index as usize)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(PyKRec { inner: krec })
+    }
+
+    /// Read only the header of episode `index`
+    fn read_header(&self, index: usize) -> PyResult<PyKRecHeader> {
+        let header = self
+            .inner
+            .read_header(index)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(PyKRecHeader { inner: header })
+    }
+
+    /// Episodes as dicts with uuid, task, frame_count and pieces, a list of dicts with shard,
+    /// name, frame_start, frame_end, offset and size
+    fn episodes<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.inner
+            .episodes()
+            .iter()
+            .map(|episode| {
+                let pieces = episode
+                    .pieces
+                    .iter()
+                    .map(|piece| {
+                        let dict = PyDict::new_bound(py);
+                        dict.set_item("shard", piece.shard.to_string_lossy().to_string())?;
+                        dict.set_item("name", piece.member.name.clone())?;
+                        dict.set_item("frame_start", piece.member.frame_start)?;
+                        dict.set_item("frame_end", piece.member.frame_end)?;
+                        dict.set_item("offset", piece.member.offset)?;
+                        dict.set_item("size", piece.member.size)?;
+                        Ok(dict)
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+                let dict = PyDict::new_bound(py);
+                dict.set_item("uuid", episode.uuid.clone())?;
+                dict.set_item("task", episode.task.clone())?;
+                dict.set_item("frame_count", episode.frame_count)?;
+                dict.set_item("pieces", pieces)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Load the recording with `uuid`, joining its pieces
+    fn load_recording(&self, uuid: &str) -> PyResult<PyKRec> {
+        let krec = self
+            .inner
+            .load_recording(uuid)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(PyKRec { inner: krec })
+    }
+
+    /// Indices of the episodes with `uuid`, in shard order
+    fn find_uuid(&self, uuid: &str) -> Vec<usize> {
+        self.inner.find_uuid(uuid)
+    }
+
+    /// Episode indices in a deterministic order for `seed`
+    fn shuffled(&self, seed: u64) -> Vec<usize> {
+        self.inner.shuffled(seed)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ShardedDataset(episodes={})", self.inner.len())
+    }
+}
+
 /// Iterator for frames
 #[gen_stub_pyclass]
 #[pyclass]
@@ -1342,6 +2035,208 @@ impl FrameIterator {
     }
 }
 
+fn catalog_entry_to_dict<'py>(
+    py: Python<'py>,
+    entry: &CatalogEntry,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("path", entry.path.clone())?;
+    dict.set_item("uuid", entry.uuid.clone())?;
+    dict.set_item("task", entry.task.clone())?;
+    dict.set_item("robot_platform", entry.robot_platform.clone())?;
+    dict.set_item("robot_serial", entry.robot_serial.clone())?;
+    dict.set_item("start_timestamp", entry.start_timestamp)?;
+    dict.set_item("end_timestamp", entry.end_timestamp)?;
+    dict.set_item("frame_count", entry.frame_count)?;
+    dict.set_item("actuator_ids", entry.actuator_ids.clone())?;
+    dict.set_item("file_size", entry.file_size)?;
+    dict.set_item("modified_ns", entry.modified_ns)?;
+    Ok(dict)
+}
+
+fn field_stats_to_dict(py: Python<'_>, stats: &Option<FieldStats>) -> PyResult<PyObject> {
+    let Some(stats) = stats else {
+        return Ok(py.None());
+    };
+    let dict = PyDict::new_bound(py);
+    dict.set_item("count", stats.count)?;
+    dict.set_item("min", stats.min)?;
+    dict.set_item("max", stats.max)?;
+    dict.set_item("mean", stats.mean)?;
+    dict.set_item("std", stats.std)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn vec3_stats_to_dict(py: Python<'_>, stats: &Option<Vec3Stats>) -> PyResult<PyObject> {
+    let Some(stats) = stats else {
+        return Ok(py.None());
+    };
+    let dict = PyDict::new_bound(py);
+    dict.set_item("x", field_stats_to_dict(py, &Some(stats.x))?)?;
+    dict.set_item("y", field_stats_to_dict(py, &Some(stats.y))?)?;
+    dict.set_item("z", field_stats_to_dict(py, &Some(stats.z))?)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn stats_to_dict<'py>(py: Python<'py>, stats: &KRecStats) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("frame_count", stats.frame_count)?;
+    dict.set_item("duration_ns", stats.duration_ns)?;
+    dict.set_item("mean_rate_hz", stats.mean_rate_hz())?;
+    dict.set_item("command_coverage", stats.command_coverage)?;
+
+    match &stats.interval {
+        Some(interval) => {
+            let interval_dict = PyDict::new_bound(py);
+            interval_dict.set_item("mean_ns", interval.mean_ns)?;
+            interval_dict.set_item("median_ns", interval.median_ns)?;
+            interval_dict.set_item("p99_ns", interval.p99_ns)?;
+            interval_dict.set_item("min_ns", interval.min_ns)?;
+            interval_dict.set_item("max_ns", interval.max_ns)?;
+            interval_dict.set_item("jitter_ns", interval.jitter_ns)?;
+            dict.set_item("interval", interval_dict)?;
+        }
+        None => dict.set_item("interval", py.None())?,
+    }
+
+    let mut gaps = Vec::new();
+    for gap in &stats.gaps {
+        let gap_dict = PyDict::new_bound(py);
+        gap_dict.set_item("frame_index", gap.frame_index)?;
+        gap_dict.set_item("interval_ns", gap.interval_ns)?;
+        gap_dict.set_item("dropped_frames", gap.dropped_frames)?;
+        gaps.push(gap_dict);
+    }
+    dict.set_item("gaps", gaps)?;
+    dict.set_item("dropped_frames", stats.dropped_frames())?;
+
+    let mut actuators = Vec::new();
+    for actuator in &stats.actuators {
+        let actuator_dict = PyDict::new_bound(py);
+        actuator_dict.set_item("actuator_id", actuator.actuator_id)?;
+        actuator_dict.set_item("name", actuator.name.clone())?;
+        actuator_dict.set_item("samples", actuator.samples)?;
+        actuator_dict.set_item("offline_fraction", actuator.offline_fraction)?;
+        actuator_dict.set_item("command_coverage", actuator.command_coverage)?;
+        actuator_dict.set_item("position", field_stats_to_dict(py, &actuator.position)?)?;
+        actuator_dict.set_item("velocity", field_stats_to_dict(py, &actuator.velocity)?)?;
+        actuator_dict.set_item("torque", field_stats_to_dict(py, &actuator.torque)?)?;
+        actuator_dict.set_item(
+            "temperature",
+            field_stats_to_dict(py, &actuator.temperature)?,
+        )?;
+        actuator_dict.set_item("voltage", field_stats_to_dict(py, &actuator.voltage)?)?;
+        actuator_dict.set_item("current", field_stats_to_dict(py, &actuator.current)?)?;
+        actuators.push(actuator_dict);
+    }
+    dict.set_item("actuators", actuators)?;
+
+    let imu = PyDict::new_bound(py);
+    imu.set_item("coverage", stats.imu.coverage)?;
+    imu.set_item("accel", vec3_stats_to_dict(py, &stats.imu.accel)?)?;
+    imu.set_item("gyro", vec3_stats_to_dict(py, &stats.imu.gyro)?)?;
+    imu.set_item("mag", vec3_stats_to_dict(py, &stats.imu.mag)?)?;
+    dict.set_item("imu", imu)?;
+
+    Ok(dict)
+}
+
+/// Builds a unit system from a default unit and per-field overrides, in `AngularField::ALL` order.
+fn parse_unit_system(angular: &str, overrides: [Option<&str>; 5]) -> PyResult<UnitSystem> {
+    let parse = |unit: &str| {
+        unit.parse::<AngularUnit>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    };
+    let mut units = UnitSystem::uniform(parse(angular)?);
+    for (field, unit) in AngularField::ALL.into_iter().zip(overrides) {
+        if let Some(unit) = unit {
+            units.set_field_unit(field, parse(unit)?);
+        }
+    }
+    Ok(units)
+}
+
+/// Extracts a (frames, width) float array in row-major order, returning it with its width.
+fn extract_matrix(
+    name: &str,
+    array: Option<&Bound<'_, PyAny>>,
+    frames: usize,
+    width: Option<usize>,
+) -> PyResult<Option<(Vec<f64>, usize)>> {
+    let Some(array) = array else {
+        return Ok(None);
+    };
+    let array = array.extract::<PyArrayLike2<f64, AllowTypeChange>>()?;
+    let view = array.as_array();
+    let (rows, cols) = view.dim();
+    if rows != frames || width.is_some_and(|width| width != cols) {
+        let expected = width.map_or("n".to_string(), |width| width.to_string());
+        return Err(PyValueError::new_err(format!(
+            "{} has shape ({}, {}), expected ({}, {})",
+            name, rows, cols, frames, expected
+        )));
+    }
+    Ok(Some((view.iter().copied().collect(), cols)))
+}
+
+fn parse_table_layout(layout: &str) -> PyResult<TableLayout> {
+    layout
+        .parse::<TableLayout>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn parse_smoothing(
+    smoothing: &str,
+    window: usize,
+    order: usize,
+    cutoff_hz: Option<f64>,
+) -> PyResult<Smoothing> {
+    match smoothing {
+        "none" => Ok(Smoothing::None),
+        "savgol" | "savitzky_golay" => Ok(Smoothing::SavitzkyGolay { window, order }),
+        "lowpass" | "low_pass" => {
+            let cutoff_hz = cutoff_hz.ok_or_else(|| {
+                PyValueError::new_err("cutoff_hz is required for low-pass smoothing")
+            })?;
+            Ok(Smoothing::LowPass { cutoff_hz })
+        }
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown smoothing '{}', expected one of: none, savgol, lowpass",
+            smoothing
+        ))),
+    }
+}
+
+fn tracking_error_to_dict(py: Python<'_>, error: &Option<TrackingError>) -> PyResult<PyObject> {
+    let Some(error) = error else {
+        return Ok(py.None());
+    };
+    let dict = PyDict::new_bound(py);
+    dict.set_item("errors", error.errors.clone())?;
+    dict.set_item("count", error.count)?;
+    dict.set_item("mean", error.mean)?;
+    dict.set_item("rms", error.rms)?;
+    dict.set_item("max_abs", error.max_abs)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn tracking_to_dict<'py>(
+    py: Python<'py>,
+    tracking: &ActuatorTracking,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("actuator_id", tracking.actuator_id)?;
+    dict.set_item("name", tracking.name.clone())?;
+    dict.set_item("kp", tracking.kp)?;
+    dict.set_item("kd", tracking.kd)?;
+    dict.set_item("position", tracking_error_to_dict(py, &tracking.position)?)?;
+    dict.set_item("velocity", tracking_error_to_dict(py, &tracking.velocity)?)?;
+    dict.set_item("torque", tracking_error_to_dict(py, &tracking.torque)?)?;
+    dict.set_item("latency_frames", tracking.latency_frames)?;
+    dict.set_item("latency_ns", tracking.latency_ns)?;
+    Ok(dict)
+}
+
 #[gen_stub_pyfunction]
 #[pyfunction]
 #[pyo3(signature = (video_path, krec_path, output_path, verbose=None))]
@@ -1366,6 +2261,105 @@ fn extract_from_video(py: Python<'_>, video_path: &str, verbose: Option<bool>) -
     Ok(PyKRec { inner: krec })
 }
 
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (video_path, verbose=None))]
+fn read_header_from_video(video_path: &str, verbose: Option<bool>) -> PyResult<PyKRecHeader> {
+    let header = ::krec::read_header_from_video(video_path, verbose)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+    Ok(PyKRecHeader { inner: header })
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (video_path, verbose=None))]
+fn frame_count_from_video(video_path: &str, verbose: Option<bool>) -> PyResult<usize> {
+    ::krec::frame_count_from_video(video_path, verbose)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (
+    inputs,
+    output_dir,
+    fps=None,
+    robot_type=None,
+    video_key="observation.images.main",
+    chunks_size=1000,
+    state_fields=None,
+    action_fields=None,
+    verbose=None
+))]
+#[allow(clippy::too_many_arguments)]
+fn export_lerobot_dataset(
+    inputs: Vec<String>,
+    output_dir: &str,
+    fps: Option<f64>,
+    robot_type: Option<String>,
+    video_key: &str,
+    chunks_size: usize,
+    state_fields: Option<Vec<String>>,
+    action_fields: Option<Vec<String>>,
+    verbose: Option<bool>,
+) -> PyResult<()> {
+    let parse_fields = |names: Vec<String>| -> PyResult<Vec<ActuatorField>> {
+        names
+            .iter()
+            .map(|name| {
+                ActuatorField::from_name(name).ok_or_else(|| {
+                    PyValueError::new_err(format!("Unknown actuator field '{}'", name))
+                })
+            })
+            .collect()
+    };
+    let defaults = LeRobotOptions::default();
+    let options = LeRobotOptions {
+        fps,
+        robot_type,
+        video_key: video_key.to_string(),
+        chunks_size,
+        state_fields: match state_fields {
+            Some(names) => parse_fields(names)?,
+            None => defaults.state_fields,
+        },
+        action_fields: match action_fields {
+            Some(names) => parse_fields(names)?,
+            None => defaults.action_fields,
+        },
+        verbose: verbose.unwrap_or(false),
+    };
+    ::krec::export_lerobot_dataset(&inputs, output_dir, &options)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (inputs, output_dir, shard_size=::krec::DEFAULT_SHARD_BYTES))]
+fn write_shards(inputs: Vec<String>, output_dir: &str, shard_size: u64) -> PyResult<Vec<String>> {
+    let mut writer = ShardWriter::create(output_dir, shard_size)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+    for path in &inputs {
+        let krec = if ::krec::is_video_file(path) {
+            ::krec::extract_from_video(path, None)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?
+        } else {
+            KRec::load(path)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?
+        };
+        writer
+            .write(&krec)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+    }
+    let shards = writer
+        .finish()
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+    Ok(shards
+        .iter()
+        .map(|shard| shard.to_string_lossy().to_string())
+        .collect())
+}
+
 #[pymodule]
 fn bindings(m: &Bound<PyModule>) -> PyResult<()> {
     let _ = ::krec::init();
@@ -1379,8 +2373,14 @@ fn bindings(m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<PyKRecHeader>()?;
     m.add_class::<PyKRec>()?;
     m.add_class::<FrameIterator>()?;
+    m.add_class::<PyCatalog>()?;
+    m.add_class::<PyShardedDataset>()?;
     m.add_function(wrap_pyfunction!(combine_with_video, m)?)?;
     m.add_function(wrap_pyfunction!(extract_from_video, m)?)?;
+    m.add_function(wrap_pyfunction!(read_header_from_video, m)?)?;
+    m.add_function(wrap_pyfunction!(frame_count_from_video, m)?)?;
+    m.add_function(wrap_pyfunction!(export_lerobot_dataset, m)?)?;
+    m.add_function(wrap_pyfunction!(write_shards, m)?)?;
 
     Ok(())
 }
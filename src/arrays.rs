@@ -0,0 +1,195 @@
+use crate::proto::{ActuatorCommand, ActuatorState, ImuValues, KRecFrame, KRecHeader};
+use crate::table::{ActuatorField, ImuField, WideLayout};
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use std::collections::HashMap;
+use tracing::{info, instrument};
+
+/// Dense row-major arrays describing every frame of a recording, with `NaN` for missing values.
+///
+/// Actuator columns follow the order of `header.actuator_configs`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameArrays<'a> {
+    /// `real_timestamp` of each frame
+    pub timestamps: &'a [u64],
+    /// `frames × actuators`
+    pub positions: Option<&'a [f64]>,
+    /// `frames × actuators`
+    pub velocities: Option<&'a [f64]>,
+    /// `frames × actuators`
+    pub torques: Option<&'a [f64]>,
+    /// `frames × actuators × 3`, as [position, velocity, torque]
+    pub commands: Option<&'a [f64]>,
+    /// `frames × imu_width`, with columns in [`ImuField::all`] order (accel, gyro, mag,
+    /// quaternion); `imu_width` may be 3, 6, 9 or 13 to omit the trailing sensors
+    pub imu: Option<&'a [f64]>,
+    pub imu_width: usize,
+}
+
+/// Checks that `data` holds `frames × width` values.
+fn check_len(name: &str, data: Option<&[f64]>, frames: usize, width: usize) -> Result<()> {
+    match data {
+        Some(data) if data.len() != frames * width => Err(eyre!(
+            "'{}' has {} values, expected {} ({} frames x {})",
+            name,
+            data.len(),
+            frames * width,
+            frames,
+            width
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Value at `index`, or `None` if the array is absent or the value is `NaN`.
+fn optional(data: Option<&[f64]>, index: usize) -> Option<f64> {
+    data.map(|d| d[index]).filter(|v| !v.is_nan())
+}
+
+impl KRec {
+    /// Actuator IDs in the default column order: the header's configs first, then any other
+    /// actuators that appear in the frames, sorted by ID.
+    pub fn actuator_ids(&self) -> Vec<u32> {
+        WideLayout::new(&self.header, &self.frames).actuator_ids
+    }
+
+    /// `real_timestamp` of every frame.
+    pub fn timestamps(&self) -> Vec<u64> {
+        self.frames.iter().map(|f| f.real_timestamp).collect()
+    }
+
+    /// Values of `field` as a dense row-major `frames × actuator_ids.len()` matrix.
+    ///
+    /// Missing actuators and unset values are `NaN`.
+    pub fn actuator_array(&self, field: ActuatorField, actuator_ids: &[u32]) -> Vec<f64> {
+        let columns: HashMap<u32, usize> = actuator_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        let width = actuator_ids.len();
+        let mut data = vec![f64::NAN; self.frames.len() * width];
+
+        for (frame, row) in self.frames.iter().zip(data.chunks_exact_mut(width.max(1))) {
+            if field.is_command() {
+                for command in &frame.actuator_commands {
+                    if let (Some(&col), Some(value)) = (
+                        columns.get(&command.actuator_id),
+                        field.get_command(command),
+                    ) {
+                        row[col] = value.as_f64();
+                    }
+                }
+            } else {
+                for state in &frame.actuator_states {
+                    if let (Some(&col), Some(value)) =
+                        (columns.get(&state.actuator_id), field.get_state(state))
+                    {
+                        row[col] = value.as_f64();
+                    }
+                }
+            }
+        }
+        data
+    }
+
+    /// IMU `fields` as a dense row-major `frames × fields.len()` matrix, `NaN` where missing.
+    pub fn imu_array(&self, fields: &[ImuField]) -> Vec<f64> {
+        self.frames
+            .iter()
+            .flat_map(|frame| {
+                fields
+                    .iter()
+                    .map(move |field| field.get(frame).unwrap_or(f64::NAN))
+            })
+            .collect()
+    }
+
+    /// Builds a recording from dense arrays in one pass.
+    ///
+    /// An actuator state is only created when at least one of its values is present, and a
+    /// command when at least one of its components is present (missing components become 0).
+    /// IMU vectors are created when any of their components is present.
+    #[instrument(skip(header, arrays))]
+    pub fn from_arrays(header: KRecHeader, arrays: &FrameArrays) -> Result<Self> {
+        let frames = arrays.timestamps.len();
+        let actuator_ids: Vec<u32> = header
+            .actuator_configs
+            .iter()
+            .map(|c| c.actuator_id)
+            .collect();
+        let actuators = actuator_ids.len();
+
+        check_len("positions", arrays.positions, frames, actuators)?;
+        check_len("velocities", arrays.velocities, frames, actuators)?;
+        check_len("torques", arrays.torques, frames, actuators)?;
+        check_len("commands", arrays.commands, frames, actuators * 3)?;
+        if arrays.imu.is_some() && ![3, 6, 9, 13].contains(&arrays.imu_width) {
+            return Err(eyre!(
+                "IMU arrays must have 3, 6, 9 or 13 columns, got {}",
+                arrays.imu_width
+            ));
+        }
+        check_len("imu", arrays.imu, frames, arrays.imu_width)?;
+
+        info!(
+            "Building KRec from arrays with {} frames and {} actuators",
+            frames, actuators
+        );
+        let imu_fields = ImuField::all();
+        let mut krec = KRec::new(header);
+        krec.frames.reserve(frames);
+
+        for (row, &timestamp) in arrays.timestamps.iter().enumerate() {
+            let mut frame = KRecFrame {
+                real_timestamp: timestamp,
+                ..Default::default()
+            };
+
+            for (col, &actuator_id) in actuator_ids.iter().enumerate() {
+                let index = row * actuators + col;
+                let position = optional(arrays.positions, index);
+                let velocity = optional(arrays.velocities, index);
+                let torque = optional(arrays.torques, index);
+                if position.is_some() || velocity.is_some() || torque.is_some() {
+                    frame.actuator_states.push(ActuatorState {
+                        actuator_id,
+                        online: true,
+                        position,
+                        velocity,
+                        torque,
+                        ..Default::default()
+                    });
+                }
+
+                let command: Vec<Option<f64>> = (0..3)
+                    .map(|k| optional(arrays.commands, index * 3 + k))
+                    .collect();
+                if command.iter().any(Option::is_some) {
+                    let component = |k: usize| command[k].unwrap_or(0.0) as f32;
+                    frame.actuator_commands.push(ActuatorCommand {
+                        actuator_id,
+                        position: component(0),
+                        velocity: component(1),
+                        torque: component(2),
+                    });
+                }
+            }
+
+            if let Some(imu) = arrays.imu {
+                let values = &imu[row * arrays.imu_width..(row + 1) * arrays.imu_width];
+                let mut imu_values: Option<ImuValues> = None;
+                for (field, value) in imu_fields.iter().zip(values) {
+                    if !value.is_nan() {
+                        field.set(imu_values.get_or_insert_with(ImuValues::default), *value);
+                    }
+                }
+                frame.imu_values = imu_values;
+            }
+
+            krec.frames.push(frame);
+        }
+
+        Ok(krec)
+    }
+}
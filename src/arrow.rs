@@ -0,0 +1,534 @@
+use crate::proto::{ActuatorCommand, ActuatorState, KRecFrame, KRecHeader};
+use crate::table::{ActuatorField, Column, ColumnType, FrameField, ImuField, Value, WideLayout};
+use crate::KRec;
+use arrow::array::{
+    ArrayRef, AsArray, BooleanBuilder, Float32Builder, Float64Builder, StringBuilder,
+    UInt32Builder, UInt64Builder,
+};
+use arrow::compute::{cast, concat_batches};
+use arrow::datatypes::{DataType, Field, Float32Type, Float64Type, Schema, UInt64Type};
+use arrow::record_batch::RecordBatch;
+use color_eyre::{eyre::eyre, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// Shape of the table produced from a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableLayout {
+    /// One row per frame and actuator, with frame fields and IMU values repeated on each row.
+    ///
+    /// Frames without any actuator data get a single row with a null `actuator_id`.
+    #[default]
+    Long,
+    /// One row per frame, with one column per actuator field (see [`WideLayout`])
+    Wide,
+}
+
+impl FromStr for TableLayout {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "long" => Ok(Self::Long),
+            "wide" => Ok(Self::Wide),
+            _ => Err(eyre!(
+                "Unknown table layout '{}', expected 'long' or 'wide'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TableLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Long => write!(f, "long"),
+            Self::Wide => write!(f, "wide"),
+        }
+    }
+}
+
+fn data_type(ty: ColumnType) -> DataType {
+    match ty {
+        ColumnType::UInt64 => DataType::UInt64,
+        ColumnType::Bool => DataType::Boolean,
+        ColumnType::Float32 => DataType::Float32,
+        ColumnType::Float64 => DataType::Float64,
+    }
+}
+
+/// Reads a column as one optional value per row, casting it to `ty`. `NaN` counts as missing.
+fn column_values(array: &ArrayRef, ty: ColumnType) -> Result<Vec<Option<Value>>> {
+    let array = cast(array, &data_type(ty))?;
+    Ok(match ty {
+        ColumnType::UInt64 => array
+            .as_primitive::<UInt64Type>()
+            .iter()
+            .map(|v| v.map(Value::UInt64))
+            .collect(),
+        ColumnType::Bool => array
+            .as_boolean()
+            .iter()
+            .map(|v| v.map(Value::Bool))
+            .collect(),
+        ColumnType::Float32 => array
+            .as_primitive::<Float32Type>()
+            .iter()
+            .map(|v| v.filter(|v| !v.is_nan()).map(Value::Float32))
+            .collect(),
+        ColumnType::Float64 => array
+            .as_primitive::<Float64Type>()
+            .iter()
+            .map(|v| v.filter(|v| !v.is_nan()).map(Value::Float64))
+            .collect(),
+    })
+}
+
+/// Array builder for a single table column.
+enum ColumnBuilder {
+    UInt64(UInt64Builder),
+    Bool(BooleanBuilder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+}
+
+impl ColumnBuilder {
+    fn new(ty: ColumnType, capacity: usize) -> Self {
+        match ty {
+            ColumnType::UInt64 => Self::UInt64(UInt64Builder::with_capacity(capacity)),
+            ColumnType::Bool => Self::Bool(BooleanBuilder::with_capacity(capacity)),
+            ColumnType::Float32 => Self::Float32(Float32Builder::with_capacity(capacity)),
+            ColumnType::Float64 => Self::Float64(Float64Builder::with_capacity(capacity)),
+        }
+    }
+
+    fn append(&mut self, value: Option<Value>) {
+        match self {
+            Self::UInt64(b) => b.append_option(value.map(|v| match v {
+                Value::UInt64(v) => v,
+                other => other.as_f64() as u64,
+            })),
+            Self::Bool(b) => b.append_option(value.map(|v| v.as_f64() != 0.0)),
+            Self::Float32(b) => b.append_option(value.map(|v| v.as_f64() as f32)),
+            Self::Float64(b) => b.append_option(value.map(|v| v.as_f64())),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::UInt64(b) => Arc::new(b.finish()),
+            Self::Bool(b) => Arc::new(b.finish()),
+            Self::Float32(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Actuators present in a frame: states first, then commands without a matching state.
+fn frame_actuator_ids(frame: &KRecFrame) -> Vec<u32> {
+    let mut ids: Vec<u32> = frame
+        .actuator_states
+        .iter()
+        .map(|s| s.actuator_id)
+        .collect();
+    for command in &frame.actuator_commands {
+        if !ids.contains(&command.actuator_id) {
+            ids.push(command.actuator_id);
+        }
+    }
+    ids
+}
+
+impl KRec {
+    /// Converts the recording into a single Arrow `RecordBatch`.
+    ///
+    /// The schema metadata carries the header (see [`KRecHeader::to_metadata`]).
+    #[instrument(skip(self))]
+    pub fn to_record_batch(&self, layout: TableLayout) -> Result<RecordBatch> {
+        let batch = match layout {
+            TableLayout::Long => self.long_record_batch()?,
+            TableLayout::Wide => self.wide_record_batch()?,
+        };
+        debug!(
+            "Built {} record batch with {} rows and {} columns",
+            layout,
+            batch.num_rows(),
+            batch.num_columns()
+        );
+        Ok(batch)
+    }
+
+    fn wide_record_batch(&self) -> Result<RecordBatch> {
+        let layout = WideLayout::new(&self.header, &self.frames);
+        let fields: Vec<Field> = layout
+            .columns
+            .iter()
+            .map(|column| {
+                Field::new(
+                    layout.column_name(column),
+                    data_type(column.column_type()),
+                    !matches!(column, Column::Frame(_)),
+                )
+            })
+            .collect();
+
+        let mut builders: Vec<ColumnBuilder> = layout
+            .columns
+            .iter()
+            .map(|column| ColumnBuilder::new(column.column_type(), self.frames.len()))
+            .collect();
+        for frame in &self.frames {
+            for (builder, column) in builders.iter_mut().zip(&layout.columns) {
+                builder.append(layout.value(column, frame));
+            }
+        }
+
+        let schema = Schema::new(fields).with_metadata(self.header.to_metadata());
+        let arrays = builders.iter_mut().map(ColumnBuilder::finish).collect();
+        Ok(RecordBatch::try_new(Arc::new(schema), arrays)?)
+    }
+
+    fn long_record_batch(&self) -> Result<RecordBatch> {
+        let names: HashMap<u32, &str> = self
+            .header
+            .actuator_configs
+            .iter()
+            .filter_map(|c| c.name.as_deref().map(|name| (c.actuator_id, name)))
+            .collect();
+        let imu_fields = ImuField::all();
+        let rows: usize = self
+            .frames
+            .iter()
+            .map(|f| frame_actuator_ids(f).len().max(1))
+            .sum();
+
+        let mut frame_index = UInt64Builder::with_capacity(rows);
+        let mut frame_fields: Vec<UInt64Builder> = FrameField::ALL
+            .iter()
+            .map(|_| UInt64Builder::with_capacity(rows))
+            .collect();
+        let mut actuator_id = UInt32Builder::with_capacity(rows);
+        let mut actuator_name = StringBuilder::new();
+        let mut actuator_fields: Vec<ColumnBuilder> = ActuatorField::ALL
+            .iter()
+            .map(|field| ColumnBuilder::new(field.column_type(), rows))
+            .collect();
+
+        let mut imu_builders: Vec<Float64Builder> = imu_fields
+            .iter()
+            .map(|_| Float64Builder::with_capacity(rows))
+            .collect();
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            let ids = frame_actuator_ids(frame);
+            // A frame without actuators still gets a row, so it survives the round trip
+            let ids: Vec<Option<u32>> = if ids.is_empty() {
+                vec![None]
+            } else {
+                ids.into_iter().map(Some).collect()
+            };
+            for id in ids {
+                frame_index.append_value(index as u64);
+                for (builder, field) in frame_fields.iter_mut().zip(FrameField::ALL) {
+                    builder.append_value(field.get(frame));
+                }
+                actuator_id.append_option(id);
+                actuator_name.append_option(id.and_then(|id| names.get(&id)));
+                for (builder, field) in actuator_fields.iter_mut().zip(ActuatorField::ALL) {
+                    builder.append(id.and_then(|id| field.get(frame, id)));
+                }
+                for (builder, field) in imu_builders.iter_mut().zip(&imu_fields) {
+                    builder.append_option(field.get(frame));
+                }
+            }
+        }
+
+        let mut fields = vec![Field::new("frame_index", DataType::UInt64, false)];
+        fields.extend(
+            FrameField::ALL
+                .iter()
+                .map(|field| Field::new(field.name(), DataType::UInt64, false)),
+        );
+        fields.push(Field::new("actuator_id", DataType::UInt32, true));
+        fields.push(Field::new("actuator_name", DataType::Utf8, true));
+        fields.extend(
+            ActuatorField::ALL
+                .iter()
+                .map(|field| Field::new(field.name(), data_type(field.column_type()), true)),
+        );
+        fields.extend(
+            imu_fields
+                .iter()
+                .map(|field| Field::new(field.name(), DataType::Float64, true)),
+        );
+
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(frame_index.finish())];
+        arrays.extend(
+            frame_fields
+                .iter_mut()
+                .map(|b| Arc::new(b.finish()) as ArrayRef),
+        );
+        arrays.push(Arc::new(actuator_id.finish()));
+        arrays.push(Arc::new(actuator_name.finish()));
+        arrays.extend(actuator_fields.iter_mut().map(ColumnBuilder::finish));
+        arrays.extend(
+            imu_builders
+                .iter_mut()
+                .map(|b| Arc::new(b.finish()) as ArrayRef),
+        );
+
+        let schema = Schema::new(fields).with_metadata(self.header.to_metadata());
+        Ok(RecordBatch::try_new(Arc::new(schema), arrays)?)
+    }
+
+    /// Rebuilds a recording from record batches in either layout.
+    ///
+    /// Batches containing an `actuator_id` column are read as the long layout, where consecutive
+    /// rows with the same `frame_index` (or `real_timestamp` if absent) form one frame, and rows
+    /// with a null `actuator_id` only carry frame and IMU values. Other
+    /// batches are read as the wide layout, resolving named actuator columns via `header`.
+    #[instrument(skip(header, batches))]
+    pub fn from_record_batches(header: KRecHeader, batches: &[RecordBatch]) -> Result<Self> {
+        let mut krec = KRec::new(header);
+        let Some(first) = batches.first() else {
+            return Ok(krec);
+        };
+        let batch = concat_batches(&first.schema(), batches)?;
+        krec.frames = if batch.schema().column_with_name("actuator_id").is_some() {
+            frames_from_long_batch(&batch)?
+        } else {
+            frames_from_wide_batch(&krec.header, &batch)?
+        };
+        debug!("Read {} frames from record batches", krec.frames.len());
+        Ok(krec)
+    }
+}
+
+fn frames_from_wide_batch(header: &KRecHeader, batch: &RecordBatch) -> Result<Vec<KRecFrame>> {
+    let schema = batch.schema();
+    let names: Vec<String> = schema.fields().iter().map(|f| f.name().clone()).collect();
+    let layout = WideLayout::from_column_names(header, &names)?;
+    let columns: Vec<Vec<Option<Value>>> = layout
+        .columns
+        .iter()
+        .zip(batch.columns())
+        .map(|(column, array)| column_values(array, column.column_type()))
+        .collect::<Result<_>>()?;
+
+    (0..batch.num_rows())
+        .map(|row| {
+            let values: Vec<Option<Value>> = columns.iter().map(|column| column[row]).collect();
+            layout.frame_from_row(&values)
+        })
+        .collect()
+}
+
+fn frames_from_long_batch(batch: &RecordBatch) -> Result<Vec<KRecFrame>> {
+    let schema = batch.schema();
+    let imu_fields = ImuField::all();
+    let unknown: Vec<&str> = schema
+        .fields()
+        .iter()
+        .map(|f| f.name().as_str())
+        .filter(|name| {
+            !matches!(*name, "frame_index" | "actuator_id" | "actuator_name")
+                && !FrameField::ALL.iter().any(|f| f.name() == *name)
+                && ActuatorField::from_name(name).is_none()
+                && !imu_fields.iter().any(|f| f.name() == *name)
+        })
+        .collect();
+    if !unknown.is_empty() {
+        return Err(eyre!("Unrecognised columns: {}", unknown.join(", ")));
+    }
+
+    let column = |name: &str, ty: ColumnType| -> Result<Option<Vec<Option<Value>>>> {
+        schema
+            .index_of(name)
+            .ok()
+            .map(|i| column_values(batch.column(i), ty))
+            .transpose()
+    };
+    let actuator_ids = column("actuator_id", ColumnType::UInt64)?
+        .ok_or_else(|| eyre!("Missing required column 'actuator_id'"))?;
+    let frame_fields: Vec<(FrameField, Vec<Option<Value>>)> = FrameField::ALL
+        .into_iter()
+        .filter_map(|f| {
+            column(f.name(), ColumnType::UInt64)
+                .transpose()
+                .map(|c| Ok((f, c?)))
+        })
+        .collect::<Result<_>>()?;
+    let actuator_fields: Vec<(ActuatorField, Vec<Option<Value>>)> = ActuatorField::ALL
+        .into_iter()
+        .filter_map(|f| {
+            column(f.name(), f.column_type())
+                .transpose()
+                .map(|c| Ok((f, c?)))
+        })
+        .collect::<Result<_>>()?;
+    let imu_columns: Vec<(ImuField, Vec<Option<Value>>)> = imu_fields
+        .into_iter()
+        .filter_map(|f| {
+            column(&f.name(), ColumnType::Float64)
+                .transpose()
+                .map(|c| Ok((f, c?)))
+        })
+        .collect::<Result<_>>()?;
+
+    let timestamps = frame_fields
+        .iter()
+        .find(|(f, _)| *f == FrameField::RealTimestamp)
+        .map(|(_, values)| values)
+        .ok_or_else(|| eyre!("Missing required column 'real_timestamp'"))?;
+    let frame_index = column("frame_index", ColumnType::UInt64)?;
+    let keys = frame_index.as_ref().unwrap_or(timestamps);
+
+    let mut frames: Vec<KRecFrame> = Vec::new();
+    for row in 0..batch.num_rows() {
+        if row == 0 || keys[row] != keys[row - 1] {
+            let mut frame = KRecFrame::default();
+            for (field, values) in &frame_fields {
+                if let Some(value) = values[row] {
+                    field.set(&mut frame, value.as_u64()?);
+                }
+            }
+            // IMU values are repeated on every row of a frame, so the first row is enough
+            for (field, values) in &imu_columns {
+                if let Some(value) = values[row] {
+                    field.set(
+                        frame.imu_values.get_or_insert_with(Default::default),
+                        value.as_f64(),
+                    );
+                }
+            }
+            frames.push(frame);
+        }
+        let frame = frames.last_mut().unwrap();
+
+        let Some(actuator_id) = actuator_ids[row] else {
+            continue;
+        };
+        let actuator_id = actuator_id.as_u64()? as u32;
+        let mut state: Option<ActuatorState> = None;
+        let mut command: Option<ActuatorCommand> = None;
+        for (field, values) in &actuator_fields {
+            let Some(value) = values[row] else {
+                continue;
+            };
+            if field.is_command() {
+                let command = command.get_or_insert_with(|| ActuatorCommand {
+                    actuator_id,
+                    ..Default::default()
+                });
+                field.set_command(command, value);
+            } else {
+                let state = state.get_or_insert_with(|| ActuatorState {
+                    actuator_id,
+                    ..Default::default()
+                });
+                field.set_state(state, value);
+            }
+        }
+        frame.actuator_states.extend(state);
+        frame.actuator_commands.extend(command);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::proto::Vec3;
+    use crate::proto::{ActuatorConfig, ImuQuaternion, ImuValues};
+
+    fn sample() -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            actuator_configs: vec![
+                ActuatorConfig {
+                    actuator_id: 3,
+                    name: Some("left_knee".to_string()),
+                    ..Default::default()
+                },
+                ActuatorConfig {
+                    actuator_id: 7,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        for i in 0..4u64 {
+            let mut frame = KRecFrame {
+                real_timestamp: 1_000_000 * i,
+                video_frame_number: i,
+                inference_step: i,
+                ..Default::default()
+            };
+            // Frame 2 has no actuator data at all
+            if i != 2 {
+                frame.actuator_states = vec![
+                    ActuatorState {
+                        actuator_id: 3,
+                        online: true,
+                        position: Some(i as f64 * 0.5),
+                        velocity: Some(1.25),
+                        ..Default::default()
+                    },
+                    ActuatorState {
+                        actuator_id: 7,
+                        online: i % 2 == 0,
+                        torque: Some(-2.0),
+                        voltage: Some(24.5),
+                        ..Default::default()
+                    },
+                ];
+                frame.actuator_commands = vec![ActuatorCommand {
+                    actuator_id: 3,
+                    position: 1.5,
+                    velocity: 0.0,
+                    torque: 0.25,
+                }];
+            }
+            // Frame 3 has no IMU data
+            if i != 3 {
+                frame.imu_values = Some(ImuValues {
+                    accel: Some(Vec3 {
+                        x: 0.1,
+                        y: 0.2,
+                        z: 9.81,
+                    }),
+                    gyro: None,
+                    mag: None,
+                    quaternion: Some(ImuQuaternion {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                        w: 1.0,
+                    }),
+                });
+            }
+            krec.add_frame(frame);
+        }
+        krec
+    }
+
+    fn round_trip(layout: TableLayout) {
+        let krec = sample();
+        let batch = krec.to_record_batch(layout).unwrap();
+        let decoded = KRec::from_record_batches(krec.header.clone(), &[batch]).unwrap();
+        assert_eq!(decoded.frames, krec.frames, "{} layout", layout);
+    }
+
+    #[test]
+    fn long_layout_round_trip() {
+        round_trip(TableLayout::Long);
+    }
+
+    #[test]
+    fn wide_layout_round_trip() {
+        round_trip(TableLayout::Wide);
+    }
+}
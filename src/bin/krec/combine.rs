@@ -0,0 +1,66 @@
+use crate::input;
+use color_eyre::{eyre::eyre, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Video file, or a directory of videos for batch mode
+    video: PathBuf,
+    /// `.krec` file, or in batch mode a directory holding `<video name>.krec` for each video
+    krec: PathBuf,
+    /// Output video, or in batch mode the output directory
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Show ffmpeg output
+    #[arg(long)]
+    verbose: bool,
+}
+
+fn combine(video: &Path, krec: &Path, output: &Path, verbose: bool) -> Result<()> {
+    krec::combine_with_video(video, krec, output, Some(verbose))?;
+    println!(
+        "{} + {} -> {}",
+        video.display(),
+        krec.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    if !args.video.is_dir() {
+        combine(&args.video, &args.krec, &args.output, args.verbose)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if !args.krec.is_dir() {
+        return Err(eyre!(
+            "In batch mode '{}' must be a directory of .krec files",
+            args.krec.display()
+        ));
+    }
+    fs::create_dir_all(&args.output)?;
+    let mut failed = 0;
+    let videos = input::videos_in(&args.video)?;
+    for video in &videos {
+        let stem = video.file_stem().unwrap_or_default().to_string_lossy();
+        let krec = args.krec.join(format!("{}.krec", stem));
+        let output = args.output.join(format!("{}.mkv", stem));
+        if let Err(e) = combine(video, &krec, &output, args.verbose) {
+            eprintln!("{}: {}", video.display(), e);
+            failed += 1;
+        }
+    }
+    println!(
+        "Combined {} of {} videos",
+        videos.len() - failed,
+        videos.len()
+    );
+    Ok(if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
@@ -0,0 +1,199 @@
+use crate::input;
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
+use krec::{AngularUnit, KRec, KRecHeader, ResampleMethod, TableLayout, UnitSystem};
+use parquet::basic::{Compression, ZstdLevel};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process::ExitCode;
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Krec,
+    Video,
+    Csv,
+    Parquet,
+    Json,
+    Ndjson,
+    Mcap,
+    Hdf5,
+}
+
+impl Format {
+    fn from_path(path: &str) -> Result<Self> {
+        if input::is_video(path) {
+            return Ok(Format::Video);
+        }
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        match ext.as_str() {
+            "krec" => Ok(Format::Krec),
+            "csv" => Ok(Format::Csv),
+            "parquet" | "pq" => Ok(Format::Parquet),
+            "json" => Ok(Format::Json),
+            "ndjson" | "jsonl" => Ok(Format::Ndjson),
+            "mcap" => Ok(Format::Mcap),
+            "h5" | "hdf5" => Ok(Format::Hdf5),
+            _ => Err(eyre!(
+                "Cannot infer format of '{}', expected one of: .krec, .mkv, .csv, .parquet, \
+                 .json, .ndjson, .mcap, .h5",
+                path
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Layout {
+    /// One row per frame and actuator
+    Long,
+    /// One row per frame
+    Wide,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Method {
+    Linear,
+    Nearest,
+    Hold,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Units {
+    Degrees,
+    Radians,
+}
+
+/// Parses `none`, `zstd` or `zstd:<level>`.
+fn parse_compression(s: &str) -> Result<Compression> {
+    match s.split_once(':') {
+        None if s == "none" => Ok(Compression::UNCOMPRESSED),
+        None if s == "zstd" => Ok(Compression::ZSTD(ZstdLevel::default())),
+        Some(("zstd", level)) => Ok(Compression::ZSTD(ZstdLevel::try_new(level.parse()?)?)),
+        _ => Err(eyre!(
+            "Unknown compression '{}', expected none, zstd or zstd:<level>",
+            s
+        )),
+    }
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Input file; the format is inferred from the extension
+    input: String,
+    /// Output file; the format is inferred from the extension
+    output: String,
+    /// Parquet compression: none, zstd or zstd:<level>
+    #[arg(long, value_parser = parse_compression)]
+    compression: Option<Compression>,
+    /// Parquet table layout; wide keeps every field in one row per frame [default: wide]
+    #[arg(long, value_enum)]
+    layout: Option<Layout>,
+    /// Resample onto a uniform grid at this rate (Hz)
+    #[arg(long, value_name = "HZ")]
+    resample: Option<f64>,
+    #[arg(long, value_enum, default_value = "linear", requires = "resample")]
+    resample_method: Method,
+    /// Convert all angular values to these units
+    #[arg(long, value_enum)]
+    units: Option<Units>,
+    /// Video to combine with when writing a video file
+    #[arg(long)]
+    video: Option<String>,
+    /// Recording whose header is used when reading CSV
+    #[arg(long)]
+    header: Option<String>,
+    /// Show ffmpeg output
+    #[arg(long)]
+    verbose: bool,
+}
+
+fn read(path: &str, format: Format, header: Option<KRecHeader>) -> Result<KRec> {
+    match format {
+        Format::Krec | Format::Video => input::load(path),
+        Format::Csv => KRec::from_csv(path, header.unwrap_or_default()),
+        Format::Parquet => KRec::from_parquet(path),
+        Format::Json => KRec::from_json(path),
+        Format::Ndjson => KRec::from_ndjson(path),
+        Format::Mcap => KRec::from_mcap(path),
+        Format::Hdf5 => KRec::from_hdf5(path, &krec::Hdf5Layout::default()),
+    }
+}
+
+fn write(krec: &KRec, path: &str, format: Format, args: &Args) -> Result<()> {
+    let layout = match args.layout {
+        Some(Layout::Long) => TableLayout::Long,
+        Some(Layout::Wide) | None => TableLayout::Wide,
+    };
+    match format {
+        Format::Krec => krec.save(path),
+        Format::Video => {
+            let video = args
+                .video
+                .as_ref()
+                .ok_or_else(|| eyre!("Writing a video requires --video"))?;
+            let temp = tempfile::Builder::new().suffix(".krec").tempfile()?;
+            let temp_path = temp.path().to_string_lossy().to_string();
+            krec.save(&temp_path)?;
+            krec::combine_with_video(video, &temp_path, path, Some(args.verbose))
+        }
+        Format::Csv => krec.to_csv(path),
+        Format::Parquet => match args.compression {
+            Some(compression) => krec.write_parquet_with_compression(
+                BufWriter::new(File::create(path)?),
+                layout,
+                compression,
+            ),
+            None => krec.to_parquet(path, layout),
+        },
+        Format::Json => krec.to_json(path),
+        Format::Ndjson => krec.to_ndjson(path),
+        Format::Mcap => krec.to_mcap(path),
+        Format::Hdf5 => krec.to_hdf5(path, &krec::Hdf5Layout::default()),
+    }
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    let from = Format::from_path(&args.input)?;
+    let to = Format::from_path(&args.output)?;
+    if args.compression.is_some() && to != Format::Parquet {
+        return Err(eyre!("--compression only applies to Parquet output"));
+    }
+    if args.layout.is_some() && to != Format::Parquet {
+        return Err(eyre!("--layout only applies to Parquet output"));
+    }
+    if args.video.is_some() && to != Format::Video {
+        return Err(eyre!("--video only applies to video output"));
+    }
+
+    let header = match &args.header {
+        Some(path) => Some(input::load(path)?.header),
+        None => None,
+    };
+    let mut krec = read(&args.input, from, header)?;
+    info!("Read {} frames from {}", krec.frames.len(), args.input);
+
+    if let Some(rate_hz) = args.resample {
+        let method = match args.resample_method {
+            Method::Linear => ResampleMethod::Linear,
+            Method::Nearest => ResampleMethod::Nearest,
+            Method::Hold => ResampleMethod::Hold,
+        };
+        krec = krec.resample(rate_hz, method)?;
+    }
+    if let Some(units) = args.units {
+        krec.convert_units(&UnitSystem::uniform(match units {
+            Units::Degrees => AngularUnit::Degrees,
+            Units::Radians => AngularUnit::Radians,
+        }));
+    }
+
+    write(&krec, &args.output, to, &args)?;
+    info!("Wrote {} frames to {}", krec.frames.len(), args.output);
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,161 @@
+use crate::input;
+use color_eyre::{eyre::eyre, Result};
+use krec::ContentHasher;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tracing::warn;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` files, videos with a KRec attachment, or directories to scan recursively
+    #[arg(required = true)]
+    paths: Vec<String>,
+    /// For each duplicate group, create a directory of symlinks to its files
+    #[arg(long)]
+    link_dir: Option<PathBuf>,
+    /// Print one JSON object per duplicate group
+    #[arg(long)]
+    json: bool,
+}
+
+/// Files sharing a UUID or content hash.
+struct Group {
+    kind: &'static str,
+    key: String,
+    paths: Vec<String>,
+}
+
+fn link_group(dir: &Path, index: usize, group: &Group) -> Result<()> {
+    let short_key: String = group.key.chars().take(16).collect();
+    let group_dir = dir.join(format!("{:04}-{}-{}", index, group.kind, short_key));
+    fs::create_dir_all(&group_dir)?;
+    for (i, path) in group.paths.iter().enumerate() {
+        let target = fs::canonicalize(path)?;
+        let name = target
+            .file_name()
+            .ok_or_else(|| eyre!("'{}' has no file name", path))?
+            .to_string_lossy()
+            .to_string();
+        let link = group_dir.join(format!("{}-{}", i, name));
+        if link.symlink_metadata().is_ok() {
+            fs::remove_file(&link)?;
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, &link)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(&target, &link)?;
+    }
+    Ok(())
+}
+
+/// Exits with status 0 if there are no duplicates, 1 if any duplicate group is found and 2 if
+/// a file could not be read, like `diff(1)`.
+pub fn run(args: Args) -> Result<ExitCode> {
+    let mut candidates = Vec::new();
+    for path in &args.paths {
+        if Path::new(path).is_dir() {
+            let found = krec::find_recordings(path)?;
+            candidates.extend(found.iter().map(|p| p.to_string_lossy().to_string()));
+        } else {
+            candidates.push(path.clone());
+        }
+    }
+
+    // A file reached through several arguments, or through a symlink, is only hashed once
+    let mut failed = false;
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    for path in candidates {
+        match fs::canonicalize(&path) {
+            Ok(canonical) => {
+                if seen.insert(canonical) {
+                    files.push(path);
+                }
+            }
+            Err(e) => {
+                warn!("Skipping {}: {}", path, e);
+                failed = true;
+            }
+        }
+    }
+
+    let mut by_uuid: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut by_content: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in &files {
+        let (header, frames) = match input::open_frames(path) {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Skipping {}: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+        let mut hasher = ContentHasher::new();
+        let mut error = None;
+        for frame in frames {
+            match frame {
+                Ok(frame) => hasher.update(&frame),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        if let Some(e) = error {
+            warn!("Skipping {}: {}", path, e);
+            failed = true;
+            continue;
+        }
+        if !header.uuid.is_empty() {
+            by_uuid.entry(header.uuid).or_default().push(path.clone());
+        }
+        by_content
+            .entry(hasher.finish())
+            .or_default()
+            .push(path.clone());
+    }
+
+    let groups: Vec<Group> = [("uuid", by_uuid), ("content", by_content)]
+        .into_iter()
+        .flat_map(|(kind, map)| {
+            map.into_iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(move |(key, paths)| Group { kind, key, paths })
+        })
+        .collect();
+
+    for (i, group) in groups.iter().enumerate() {
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string(&json!({
+                    "kind": group.kind,
+                    "key": group.key,
+                    "paths": group.paths,
+                }))?
+            );
+        } else {
+            println!("{} {} ({} files)", group.kind, group.key, group.paths.len());
+            for path in &group.paths {
+                println!("  {}", path);
+            }
+        }
+        if let Some(dir) = &args.link_dir {
+            link_group(dir, i, group)?;
+        }
+    }
+    if !args.json {
+        println!("{} files, {} duplicate groups", files.len(), groups.len());
+    }
+
+    Ok(if failed {
+        ExitCode::from(2)
+    } else if !groups.is_empty() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
@@ -0,0 +1,45 @@
+use crate::input;
+use color_eyre::Result;
+use serde_json::json;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// First recording (`.krec` or video with a KRec attachment)
+    left: String,
+    /// Second recording
+    right: String,
+    /// Largest absolute difference treated as equal
+    #[arg(long, default_value_t = 0.0)]
+    tolerance: f64,
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+/// Exits with status 1 if the recordings differ, like `diff(1)`.
+pub fn run(args: Args) -> Result<ExitCode> {
+    let left = input::load(&args.left)?;
+    let right = input::load(&args.right)?;
+    let diff = left.diff(&right, args.tolerance);
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string(&json!({
+                "left": args.left,
+                "right": args.right,
+                "identical": diff.is_identical(),
+                "diff": diff,
+            }))?
+        );
+    } else {
+        print!("{}", diff);
+    }
+
+    Ok(if diff.is_identical() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,244 @@
+use crate::input;
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
+use krec::{ActuatorField, Column, FrameField, ImuField, Value, WideLayout};
+use serde_json::{Map, Value as JsonValue};
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Aligned text columns
+    Table,
+    /// One JSON object per frame
+    Ndjson,
+}
+
+/// Half-open range of frame indices, such as `100..120`, `100..`, `..120` or `100`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameRange {
+    start: usize,
+    end: Option<usize>,
+}
+
+impl FromStr for FrameRange {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |s: &str| -> Result<Option<usize>> {
+            let s = s.trim();
+            if s.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(
+                    s.parse()
+                        .map_err(|_| eyre!("Invalid frame index '{}'", s))?,
+                ))
+            }
+        };
+        // End (exclusive) of a range whose last frame is `index`
+        let after = |index: usize| {
+            index
+                .checked_add(1)
+                .ok_or_else(|| eyre!("Frame index {} is too large", index))
+        };
+        let range = match s.split_once("..") {
+            Some((start, end)) => match end.strip_prefix('=') {
+                Some(end) => FrameRange {
+                    start: parse(start)?.unwrap_or(0),
+                    end: parse(end)?.map(after).transpose()?,
+                },
+                None => FrameRange {
+                    start: parse(start)?.unwrap_or(0),
+                    end: parse(end)?,
+                },
+            },
+            None => {
+                let index = parse(s)?.ok_or_else(|| eyre!("Empty frame range"))?;
+                FrameRange {
+                    start: index,
+                    end: Some(after(index)?),
+                }
+            }
+        };
+        Ok(range)
+    }
+}
+
+fn parse_field(name: &str) -> Result<ActuatorField> {
+    ActuatorField::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = ActuatorField::ALL.iter().map(|f| f.name()).collect();
+        eyre!(
+            "Unknown field '{}', expected one of: {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` file, video with a KRec attachment, or `-` for stdin
+    #[arg(default_value = "-")]
+    file: String,
+    /// Frames to print, e.g. `100..120`, `100..`, `..120` or `100`
+    #[arg(long)]
+    frames: Option<FrameRange>,
+    /// Actuator IDs to print (defaults to all actuators in the header)
+    #[arg(long, value_delimiter = ',')]
+    actuators: Vec<u32>,
+    /// Actuator fields to print
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_field,
+        default_value = "position,velocity,torque"
+    )]
+    fields: Vec<ActuatorField>,
+    /// Also print IMU values
+    #[arg(long)]
+    imu: bool,
+    #[arg(long, value_enum, default_value = "table")]
+    format: Format,
+}
+
+fn json_value(value: Value) -> JsonValue {
+    match value {
+        Value::UInt64(v) => v.into(),
+        Value::Bool(v) => v.into(),
+        Value::Float32(v) => f64::from(v).into(),
+        Value::Float64(v) => v.into(),
+    }
+}
+
+fn table_cell(value: Option<Value>) -> String {
+    match value {
+        Some(Value::Float32(v)) => format!("{:.4}", v),
+        Some(Value::Float64(v)) => format!("{:.4}", v),
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn dump(args: &Args, out: &mut impl Write) -> Result<()> {
+    let (header, frames) = input::open_frames(&args.file)?;
+    let actuator_ids = if args.actuators.is_empty() {
+        header
+            .actuator_configs
+            .iter()
+            .map(|c| c.actuator_id)
+            .collect()
+    } else {
+        args.actuators.clone()
+    };
+    let layout = WideLayout::with_actuators(&header, actuator_ids.clone());
+
+    let mut columns = vec![Column::Frame(FrameField::RealTimestamp)];
+    for &actuator_id in &actuator_ids {
+        for &field in &args.fields {
+            columns.push(Column::Actuator { actuator_id, field });
+        }
+    }
+    if args.imu {
+        columns.extend(ImuField::all().into_iter().map(Column::Imu));
+    }
+    let names: Vec<String> = columns.iter().map(|c| layout.column_name(c)).collect();
+    let widths: Vec<usize> = names.iter().map(|n| n.len().max(10)).collect();
+
+    if let Format::Table = args.format {
+        write!(out, "{:>8}", "frame")?;
+        for (name, width) in names.iter().zip(&widths) {
+            write!(out, " {:>width$}", name, width = *width)?;
+        }
+        writeln!(out)?;
+    }
+
+    let range = args.frames.unwrap_or(FrameRange {
+        start: 0,
+        end: None,
+    });
+    let count = range
+        .end
+        .map_or(usize::MAX, |end| end.saturating_sub(range.start));
+    for (i, frame) in frames.skip(range.start).take(count).enumerate() {
+        let frame = frame?;
+        let frame_index = range.start + i;
+        let values = columns.iter().map(|c| layout.value(c, &frame));
+
+        match args.format {
+            Format::Table => {
+                write!(out, "{:>8}", frame_index)?;
+                for (value, width) in values.zip(&widths) {
+                    write!(out, " {:>width$}", table_cell(value), width = *width)?;
+                }
+                writeln!(out)?;
+            }
+            Format::Ndjson => {
+                let mut row = Map::new();
+                row.insert("frame_index".to_string(), frame_index.into());
+                for (name, value) in names.iter().zip(values) {
+                    if let Some(value) = value {
+                        row.insert(name.clone(), json_value(value));
+                    }
+                }
+                serde_json::to_writer(&mut *out, &row)?;
+                writeln!(out)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    match dump(&args, &mut out) {
+        // The reader went away (e.g. `| head`), which is not an error
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => Err(e),
+        Ok(()) => Ok(ExitCode::SUCCESS),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: usize, end: Option<usize>) -> FrameRange {
+        FrameRange { start, end }
+    }
+
+    #[test]
+    fn parses_frame_ranges() {
+        for (input, expected) in [
+            ("100..120", range(100, Some(120))),
+            ("100..=120", range(100, Some(121))),
+            ("100..", range(100, None)),
+            ("..120", range(0, Some(120))),
+            ("..=120", range(0, Some(121))),
+            ("..", range(0, None)),
+            ("100", range(100, Some(101))),
+            (" 5 .. 7 ", range(5, Some(7))),
+        ] {
+            assert_eq!(input.parse::<FrameRange>().unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_frame_ranges() {
+        let max = usize::MAX.to_string();
+        for input in ["", "abc", "1..x", "-1", "1...2", max.as_str()] {
+            assert!(input.parse::<FrameRange>().is_err(), "{}", input);
+        }
+        let err = format!("..={}", usize::MAX)
+            .parse::<FrameRange>()
+            .unwrap_err();
+        assert!(err.to_string().contains("too large"));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::input;
+use color_eyre::{eyre::eyre, Result};
+use krec::{KRec, KRecHeader, KRecReader};
+use std::process::ExitCode;
+
+/// Parses a `field=value` assignment.
+fn parse_assignment(s: &str) -> Result<(String, String)> {
+    let (field, value) = s
+        .split_once('=')
+        .ok_or_else(|| eyre!("Expected field=value, got '{}'", s))?;
+    Ok((field.trim().to_string(), value.to_string()))
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` file or video with a KRec attachment, edited in place
+    file: String,
+    /// Header field to set, e.g. `--set task="pick cube"`. May be repeated
+    #[arg(long = "set", value_name = "FIELD=VALUE", value_parser = parse_assignment)]
+    assignments: Vec<(String, String)>,
+    /// Replace the UUID with a new random one
+    #[arg(long)]
+    new_uuid: bool,
+    /// Show ffmpeg output
+    #[arg(long)]
+    verbose: bool,
+}
+
+fn edit_header(path: &str, args: &Args) -> Result<()> {
+    let old = KRecReader::open(path)?.header().clone();
+    let mut header = old.clone();
+    for (field, value) in &args.assignments {
+        header.set_field(field, value)?;
+    }
+    if args.new_uuid {
+        header.uuid = uuid::Uuid::new_v4().to_string();
+    }
+
+    print_changes(&old, &header);
+    KRec::rewrite_header(path, &header)
+}
+
+fn print_changes(old: &KRecHeader, new: &KRecHeader) {
+    let fields = |h: &KRecHeader| {
+        [
+            h.uuid.clone(),
+            h.task.clone(),
+            h.robot_platform.clone(),
+            h.robot_serial.clone(),
+            h.start_timestamp.to_string(),
+            h.end_timestamp.to_string(),
+        ]
+    };
+    for ((name, old), new) in KRecHeader::EDITABLE_FIELDS
+        .iter()
+        .zip(fields(old))
+        .zip(fields(new))
+    {
+        if old != new {
+            println!("{}: {:?} -> {:?}", name, old, new);
+        }
+    }
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    if args.assignments.is_empty() && !args.new_uuid {
+        return Err(eyre!("Nothing to edit, pass --set or --new-uuid"));
+    }
+
+    if !input::is_video(&args.file) {
+        edit_header(&args.file, &args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Edit a copy of the attachment, then swap it into the video
+    let temp = tempfile::Builder::new().suffix(".krec").tempfile()?;
+    let temp_path = temp.path().to_string_lossy().to_string();
+    krec::extract_krec_attachment(&args.file, &temp_path, Some(args.verbose))?;
+    edit_header(&temp_path, &args)?;
+    krec::replace_attachment(&args.file, &temp_path, Some(args.verbose))?;
+    Ok(ExitCode::SUCCESS)
+}
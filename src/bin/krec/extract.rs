@@ -0,0 +1,75 @@
+use crate::input;
+use color_eyre::{eyre::eyre, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Video with a KRec attachment, or a directory of videos for batch mode
+    input: PathBuf,
+    /// Output `.krec` file, or in batch mode the output directory. Defaults to the input path
+    /// with a `.krec` extension, or the input directory in batch mode
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Index of the attachment to extract, among the video's attachments. Defaults to the
+    /// attachment that looks like a KRec
+    #[arg(long)]
+    attachment: Option<usize>,
+    /// Show ffmpeg output
+    #[arg(long)]
+    verbose: bool,
+}
+
+fn extract(video: &Path, output: &Path, args: &Args) -> Result<()> {
+    let video_path = video
+        .to_str()
+        .ok_or_else(|| eyre!("Invalid video path: {}", video.display()))?;
+    match args.attachment {
+        Some(attachment) => {
+            krec::extract_attachment(video_path, attachment, output, Some(args.verbose))?
+        }
+        None => krec::extract_krec_attachment(video_path, output, Some(args.verbose))?,
+    }
+    // Make sure the attachment is a readable recording
+    krec::KRec::load(&output.to_string_lossy()).map_err(|e| match args.attachment {
+        Some(attachment) => eyre!("Attachment {} is not a KRec file: {}", attachment, e),
+        None => eyre!("The KRec attachment could not be read: {}", e),
+    })?;
+    println!("{} -> {}", video.display(), output.display());
+    Ok(())
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    if !args.input.is_dir() {
+        let output = args
+            .output
+            .clone()
+            .unwrap_or_else(|| args.input.with_extension("krec"));
+        extract(&args.input, &output, &args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let output_dir = args.output.clone().unwrap_or_else(|| args.input.clone());
+    fs::create_dir_all(&output_dir)?;
+    let mut failed = 0;
+    let videos = input::videos_in(&args.input)?;
+    for video in &videos {
+        let stem = video.file_stem().unwrap_or_default().to_string_lossy();
+        let output = output_dir.join(format!("{}.krec", stem));
+        if let Err(e) = extract(video, &output, &args) {
+            eprintln!("{}: {}", video.display(), e);
+            failed += 1;
+        }
+    }
+    println!(
+        "Extracted {} of {} videos",
+        videos.len() - failed,
+        videos.len()
+    );
+    Ok(if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
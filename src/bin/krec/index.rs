@@ -0,0 +1,33 @@
+use color_eyre::Result;
+use krec::Catalog;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to scan recursively for `.krec` files and combined videos
+    dir: String,
+    /// Catalog database, created if missing
+    #[arg(long, default_value = "krec-catalog.sqlite")]
+    db: String,
+    /// Print the summary as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    let mut catalog = Catalog::open(&args.db)?;
+    let summary = catalog.index_dir(&args.dir)?;
+    if args.json {
+        println!("{}", serde_json::to_string(&summary)?);
+    } else {
+        println!(
+            "{}: {} indexed, {} unchanged, {} failed, {} removed",
+            args.db, summary.indexed, summary.unchanged, summary.failed, summary.removed
+        );
+    }
+    Ok(if summary.failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
@@ -0,0 +1,62 @@
+use crate::input;
+use color_eyre::Result;
+use serde_json::json;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` files or videos with a KRec attachment
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// Print one JSON object per file
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    for (i, path) in args.files.iter().enumerate() {
+        let krec = input::load(path)?;
+        let stats = krec.stats();
+        let header = &krec.header;
+
+        if args.json {
+            let info = json!({
+                "path": path,
+                "header": header,
+                "frame_count": stats.frame_count,
+                "duration_ns": stats.duration_ns,
+                "mean_rate_hz": stats.mean_rate_hz(),
+            });
+            println!("{}", serde_json::to_string(&info)?);
+            continue;
+        }
+
+        if i > 0 {
+            println!();
+        }
+        println!("File: {}", path);
+        println!("UUID: {}", header.uuid);
+        println!("Task: {}", header.task);
+        println!("Robot Platform: {}", header.robot_platform);
+        println!("Robot Serial: {}", header.robot_serial);
+        println!("Start Timestamp: {}", header.start_timestamp);
+        println!("End Timestamp: {}", header.end_timestamp);
+        println!("Frames: {}", stats.frame_count);
+        println!("Duration: {:.3} s", stats.duration_ns as f64 / 1e9);
+        if let Some(rate) = stats.mean_rate_hz() {
+            println!("Mean rate: {:.2} Hz", rate);
+        }
+        println!("Actuators ({}):", header.actuator_configs.len());
+        for config in &header.actuator_configs {
+            print!("  ID {}", config.actuator_id);
+            if let Some(name) = &config.name {
+                print!(" {}", name);
+            }
+            println!(
+                " (kp={:?}, kd={:?}, ki={:?}, max_torque={:?})",
+                config.kp, config.kd, config.ki, config.max_torque
+            );
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
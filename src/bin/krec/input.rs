@@ -0,0 +1,52 @@
+use color_eyre::{eyre::eyre, Result};
+use krec::{KRec, KRecFrame, KRecHeader, KRecReader};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Returns true if `path` looks like a video rather than a `.krec` file.
+pub fn is_video(path: &str) -> bool {
+    krec::is_video_file(path)
+}
+
+/// Loads a `.krec` file, or the KRec attached to a combined video.
+pub fn load(path: &str) -> Result<KRec> {
+    if is_video(path) {
+        krec::extract_from_video(path, None)
+            .map_err(|e| eyre!("Failed to extract KRec from '{}': {}", path, e))
+    } else {
+        KRec::load(path).map_err(|e| eyre!("Failed to load '{}': {}", path, e))
+    }
+}
+
+/// Header and frames of a recording.
+pub type FrameStream = (KRecHeader, Box<dyn Iterator<Item = Result<KRecFrame>>>);
+
+/// Opens a recording for reading frame by frame. `.krec` files and stdin (`-`) are streamed;
+/// videos are extracted and loaded in full.
+pub fn open_frames(path: &str) -> Result<FrameStream> {
+    if path == "-" {
+        let reader = KRecReader::new(io::stdin().lock())?;
+        Ok((reader.header().clone(), Box::new(reader)))
+    } else if is_video(path) {
+        let krec = load(path)?;
+        Ok((krec.header, Box::new(krec.frames.into_iter().map(Ok))))
+    } else {
+        let reader =
+            KRecReader::open(path).map_err(|e| eyre!("Failed to open '{}': {}", path, e))?;
+        Ok((reader.header().clone(), Box::new(reader)))
+    }
+}
+
+/// Video files directly inside `dir`, sorted by name.
+pub fn videos_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut videos = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && krec::is_video_file(&path) {
+            videos.push(path);
+        }
+    }
+    videos.sort();
+    Ok(videos)
+}
@@ -0,0 +1,90 @@
+//! Command-line tool for inspecting and converting KRec recordings.
+
+use clap::{Parser, Subcommand};
+use color_eyre::Result;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+mod combine;
+mod convert;
+mod dedup;
+mod diff;
+mod dump;
+mod edit;
+mod extract;
+mod index;
+mod info;
+mod input;
+mod query;
+mod shard;
+mod stats;
+mod validate;
+
+#[derive(Parser)]
+#[command(
+    name = "krec",
+    version,
+    about = "Inspect and convert K-Scale robot recordings"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// ffmpeg executable to use instead of `ffmpeg` from `PATH`
+    #[arg(long, global = true)]
+    ffmpeg_path: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Attach a recording to a video
+    Combine(combine::Args),
+    /// Convert between recording formats, inferred from the file extensions
+    Convert(convert::Args),
+    /// Find recordings sharing a UUID or frame content; exits 1 if any are found and 2 if a
+    /// file cannot be read
+    Dedup(dedup::Args),
+    /// Compare two recordings, exiting non-zero if they differ
+    Diff(diff::Args),
+    /// Print frames as a table or NDJSON
+    #[command(alias = "cat")]
+    Dump(dump::Args),
+    /// Edit header fields in place without re-decoding frames
+    Edit(edit::Args),
+    /// Extract the recording attached to a video
+    Extract(extract::Args),
+    /// Index the headers of recordings under a directory into a catalog database
+    Index(index::Args),
+    /// Print the header, frame count, duration and actuators
+    Info(info::Args),
+    /// List catalogued recordings matching an SQL condition
+    Query(query::Args),
+    /// Pack recordings into fixed-size tar shards for training
+    Shard(shard::Args),
+    /// Print per-actuator and IMU summary statistics
+    Stats(stats::Args),
+    /// Check recordings for structural problems, exiting non-zero if any are found
+    Validate(validate::Args),
+}
+
+fn main() -> Result<ExitCode> {
+    krec::init()?;
+    let cli = Cli::parse();
+    if let Some(path) = cli.ffmpeg_path {
+        krec::set_ffmpeg_path(path);
+    }
+    match cli.command {
+        Command::Combine(args) => combine::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Dedup(args) => dedup::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Dump(args) => dump::run(args),
+        Command::Edit(args) => edit::run(args),
+        Command::Extract(args) => extract::run(args),
+        Command::Index(args) => index::run(args),
+        Command::Info(args) => info::run(args),
+        Command::Query(args) => query::run(args),
+        Command::Shard(args) => shard::run(args),
+        Command::Stats(args) => stats::run(args),
+        Command::Validate(args) => validate::run(args),
+    }
+}
@@ -0,0 +1,54 @@
+use color_eyre::{eyre::eyre, Result};
+use krec::{Catalog, CatalogEntry};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// SQL `WHERE` clause over the catalog columns, e.g. "task = 'walk' AND frame_count > 100";
+    /// matches every recording if omitted
+    #[arg(default_value = "")]
+    filter: String,
+    /// Catalog database written by `krec index`
+    #[arg(long, default_value = "krec-catalog.sqlite")]
+    db: String,
+    /// Print one JSON object per matching recording instead of its path
+    #[arg(long)]
+    json: bool,
+}
+
+fn print(entries: &[CatalogEntry], json: bool, out: &mut impl Write) -> Result<()> {
+    for entry in entries {
+        if json {
+            serde_json::to_writer(&mut *out, entry)?;
+            writeln!(out)?;
+        } else {
+            writeln!(out, "{}", entry.path)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    if !Path::new(&args.db).exists() {
+        return Err(eyre!(
+            "Catalog {} not found, run `krec index` first",
+            args.db
+        ));
+    }
+    let entries = Catalog::open(&args.db)?.query(&args.filter)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    match print(&entries, args.json, &mut out) {
+        // The reader went away (e.g. `| head`), which is not an error
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => Err(e),
+        Ok(()) => Ok(ExitCode::SUCCESS),
+    }
+}
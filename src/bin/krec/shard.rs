@@ -0,0 +1,72 @@
+use crate::input;
+use color_eyre::{eyre::eyre, Result};
+use krec::{ShardWriter, DEFAULT_SHARD_BYTES};
+use serde_json::json;
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` files, videos with a KRec attachment, or directories to scan recursively
+    #[arg(required = true)]
+    inputs: Vec<String>,
+    /// Directory to write `shard-NNNNN.tar` files to
+    #[arg(short, long)]
+    output: String,
+    /// Maximum shard size in bytes, with an optional K, M or G suffix (powers of 1024)
+    #[arg(long, default_value_t = DEFAULT_SHARD_BYTES, value_parser = parse_size)]
+    shard_size: u64,
+    /// Print the written shards as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&value[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    let size = digits
+        .parse::<u64>()
+        .map_err(|e| format!("invalid size '{}': {}", value, e))?;
+    size.checked_mul(multiplier)
+        .filter(|&size| size > 0)
+        .ok_or_else(|| format!("invalid size '{}'", value))
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    let mut files = Vec::new();
+    for path in &args.inputs {
+        if Path::new(path).is_dir() {
+            let found = krec::find_recordings(path)?;
+            files.extend(found.iter().map(|p| p.to_string_lossy().to_string()));
+        } else {
+            files.push(path.clone());
+        }
+    }
+    if files.is_empty() {
+        return Err(eyre!("No recordings found"));
+    }
+
+    let mut writer = ShardWriter::create(&args.output, args.shard_size)?;
+    for path in &files {
+        writer.write(&input::load(path)?)?;
+    }
+    let shards = writer.finish()?;
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string(&json!({ "recordings": files.len(), "shards": shards }))?
+        );
+    } else {
+        for shard in &shards {
+            println!("{}", shard.display());
+        }
+        println!("{} recordings, {} shards", files.len(), shards.len());
+    }
+    Ok(ExitCode::SUCCESS)
+}
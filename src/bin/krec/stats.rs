@@ -0,0 +1,34 @@
+use crate::input;
+use color_eyre::Result;
+use serde_json::json;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` files or videos with a KRec attachment
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// Print one JSON object per file
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    for (i, path) in args.files.iter().enumerate() {
+        let stats = input::load(path)?.stats();
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string(&json!({ "path": path, "stats": stats }))?
+            );
+            continue;
+        }
+
+        if i > 0 {
+            println!();
+        }
+        println!("File: {}", path);
+        print!("{}", stats);
+    }
+    Ok(ExitCode::SUCCESS)
+}
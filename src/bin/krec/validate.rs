@@ -0,0 +1,74 @@
+use crate::input;
+use color_eyre::Result;
+use krec::{Severity, ValidationIssue};
+use serde_json::json;
+use std::process::ExitCode;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// `.krec` files or videos with a KRec attachment
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// Also fail on warnings
+    #[arg(long)]
+    strict: bool,
+    /// Print one JSON object per file
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    let fail_at = if args.strict {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+    let mut failed = false;
+
+    for path in &args.files {
+        // A file that cannot be read is reported like any other error
+        let issues = match input::load(path) {
+            Ok(krec) => krec.validate(),
+            Err(e) => vec![ValidationIssue {
+                severity: Severity::Error,
+                frame_index: None,
+                message: e.to_string(),
+            }],
+        };
+        let valid = issues.iter().all(|issue| issue.severity < fail_at);
+        failed |= !valid;
+
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string(&json!({
+                    "path": path,
+                    "valid": valid,
+                    "issues": issues,
+                }))?
+            );
+            continue;
+        }
+
+        for issue in &issues {
+            println!("{}: {}", path, issue);
+        }
+        let errors = issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .count();
+        println!(
+            "{}: {} ({} errors, {} warnings)",
+            path,
+            if valid { "OK" } else { "FAILED" },
+            errors,
+            issues.len() - errors
+        );
+    }
+
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
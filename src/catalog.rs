@@ -0,0 +1,394 @@
+use crate::ffmpeg::extract_krec_attachment;
+use crate::files::find_recordings;
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info, instrument, warn};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS recordings (
+    path TEXT PRIMARY KEY,
+    uuid TEXT NOT NULL,
+    task TEXT NOT NULL,
+    robot_platform TEXT NOT NULL,
+    robot_serial TEXT NOT NULL,
+    start_timestamp INTEGER NOT NULL,
+    end_timestamp INTEGER NOT NULL,
+    frame_count INTEGER NOT NULL,
+    actuator_ids TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    modified_ns INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS recordings_uuid ON recordings (uuid);
+CREATE INDEX IF NOT EXISTS recordings_task ON recordings (task);
+CREATE INDEX IF NOT EXISTS recordings_robot ON recordings (robot_platform, robot_serial);
+";
+
+const COLUMNS: &str = "path, uuid, task, robot_platform, robot_serial, start_timestamp, \
+                       end_timestamp, frame_count, actuator_ids, file_size, modified_ns";
+
+/// Header summary of one recording file, as stored in a [`Catalog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CatalogEntry {
+    pub path: String,
+    pub uuid: String,
+    pub task: String,
+    pub robot_platform: String,
+    pub robot_serial: String,
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+    pub frame_count: u64,
+    /// IDs of the actuators in the header's configs
+    pub actuator_ids: Vec<u32>,
+    pub file_size: u64,
+    /// Modification time of the file, in nanoseconds since the Unix epoch
+    pub modified_ns: u64,
+}
+
+impl CatalogEntry {
+    /// Reads the header and counts the frames of a `.krec` file or combined video, without
+    /// decoding any frames.
+    #[instrument]
+    pub fn read(path: &str) -> Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let (file_size, modified_ns) = file_stamp(&metadata);
+
+        let is_krec = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("krec"));
+        let (header, frame_count) = if is_krec {
+            (KRec::read_header(path)?, KRec::frame_count(path)?)
+        } else {
+            let temp = tempfile::NamedTempFile::new()?;
+            let temp_path = temp.path().to_string_lossy().to_string();
+            extract_krec_attachment(path, &temp_path, None)?;
+            (
+                KRec::read_header(&temp_path)?,
+                KRec::frame_count(&temp_path)?,
+            )
+        };
+
+        Ok(Self {
+            path: path.to_string(),
+            uuid: header.uuid,
+            task: header.task,
+            robot_platform: header.robot_platform,
+            robot_serial: header.robot_serial,
+            start_timestamp: header.start_timestamp,
+            end_timestamp: header.end_timestamp,
+            frame_count: frame_count as u64,
+            actuator_ids: header
+                .actuator_configs
+                .iter()
+                .map(|c| c.actuator_id)
+                .collect(),
+            file_size,
+            modified_ns,
+        })
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let actuator_ids: String = row.get(8)?;
+        Ok(Self {
+            path: row.get(0)?,
+            uuid: row.get(1)?,
+            task: row.get(2)?,
+            robot_platform: row.get(3)?,
+            robot_serial: row.get(4)?,
+            start_timestamp: row.get::<_, i64>(5)? as u64,
+            end_timestamp: row.get::<_, i64>(6)? as u64,
+            frame_count: row.get::<_, i64>(7)? as u64,
+            actuator_ids: actuator_ids
+                .split(',')
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+            file_size: row.get::<_, i64>(9)? as u64,
+            modified_ns: row.get::<_, i64>(10)? as u64,
+        })
+    }
+}
+
+/// Result of [`Catalog::index_dir`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IndexSummary {
+    /// Files read and added or updated
+    pub indexed: usize,
+    /// Files whose size and modification time were unchanged
+    pub unchanged: usize,
+    /// Files that could not be read
+    pub failed: usize,
+    /// Entries removed because their file no longer exists
+    pub removed: usize,
+}
+
+fn file_stamp(metadata: &fs::Metadata) -> (u64, u64) {
+    let modified_ns = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64);
+    (metadata.len(), modified_ns)
+}
+
+fn insert_entry(conn: &Connection, entry: &CatalogEntry) -> Result<()> {
+    let actuator_ids: Vec<String> = entry.actuator_ids.iter().map(u32::to_string).collect();
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO recordings ({}) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            COLUMNS
+        ),
+        params![
+            entry.path,
+            entry.uuid,
+            entry.task,
+            entry.robot_platform,
+            entry.robot_serial,
+            entry.start_timestamp as i64,
+            entry.end_timestamp as i64,
+            entry.frame_count as i64,
+            actuator_ids.join(","),
+            entry.file_size as i64,
+            entry.modified_ns as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// SQLite index of recording headers, for finding recordings without opening them.
+///
+/// Each file is stored under its canonical path in the `recordings` table, which has one column
+/// per [`CatalogEntry`] field; `actuator_ids` is a comma-separated list.
+pub struct Catalog {
+    conn: Connection,
+}
+
+impl Catalog {
+    /// Opens the catalog database at `path`, creating it if needed.
+    #[instrument]
+    pub fn open(path: &str) -> Result<Self> {
+        info!("Opening catalog: {}", path);
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Opens a temporary in-memory catalog.
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Adds or replaces an entry.
+    pub fn insert(&self, entry: &CatalogEntry) -> Result<()> {
+        insert_entry(&self.conn, entry)
+    }
+
+    /// Returns the entry for `path`, if any.
+    pub fn get(&self, path: &str) -> Result<Option<CatalogEntry>> {
+        Ok(self
+            .conn
+            .query_row(
+                &format!("SELECT {} FROM recordings WHERE path = ?1", COLUMNS),
+                [path],
+                CatalogEntry::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Scans `dir` recursively for `.krec` files and combined videos and indexes them.
+    ///
+    /// Files whose size and modification time match their entry are skipped, and entries under
+    /// `dir` whose file is gone are removed. Files that cannot be read are logged and counted
+    /// as failed.
+    #[instrument(skip(self))]
+    pub fn index_dir(&mut self, dir: &str) -> Result<IndexSummary> {
+        let dir = fs::canonicalize(dir)?;
+        info!("Indexing recordings under: {}", dir.display());
+        let mut summary = IndexSummary::default();
+        let mut seen = HashSet::new();
+
+        let tx = self.conn.transaction()?;
+        for path in find_recordings(&dir)? {
+            let path_str = path.to_string_lossy().to_string();
+            seen.insert(path_str.clone());
+
+            let stamp = file_stamp(&fs::metadata(&path)?);
+            let existing: Option<(i64, i64)> = tx
+                .query_row(
+                    "SELECT file_size, modified_ns FROM recordings WHERE path = ?1",
+                    [&path_str],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            if existing == Some((stamp.0 as i64, stamp.1 as i64)) {
+                summary.unchanged += 1;
+                continue;
+            }
+
+            match CatalogEntry::read(&path_str) {
+                Ok(entry) => {
+                    insert_entry(&tx, &entry)?;
+                    summary.indexed += 1;
+                }
+                Err(e) => {
+                    warn!("Skipping {}: {}", path_str, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        let prefix = format!("{}{}", dir.to_string_lossy(), std::path::MAIN_SEPARATOR);
+        let stale: Vec<String> = {
+            let mut statement = tx.prepare("SELECT path FROM recordings")?;
+            let paths = statement
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            paths
+                .into_iter()
+                .filter(|p| p.starts_with(&prefix) && !seen.contains(p))
+                .collect()
+        };
+        for path in &stale {
+            tx.execute("DELETE FROM recordings WHERE path = ?1", [path])?;
+        }
+        summary.removed = stale.len();
+        tx.commit()?;
+
+        info!(
+            "Indexed {} recordings ({} unchanged, {} failed, {} removed)",
+            summary.indexed, summary.unchanged, summary.failed, summary.removed
+        );
+        Ok(summary)
+    }
+
+    /// Returns entries matching an SQL `WHERE` clause over the `recordings` columns, e.g.
+    /// `task LIKE 'walk%' AND robot_platform = 'kbot'`, ordered by path.
+    ///
+    /// The clause is inserted into the query as is, so it must come from a trusted source.
+    #[instrument(skip(self))]
+    pub fn query(&self, where_clause: &str) -> Result<Vec<CatalogEntry>> {
+        let where_clause = if where_clause.trim().is_empty() {
+            "1"
+        } else {
+            where_clause
+        };
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {} FROM recordings WHERE {} ORDER BY path",
+            COLUMNS, where_clause
+        ))?;
+        let entries = statement
+            .query_map([], CatalogEntry::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| eyre!("Catalog query failed: {}", e))?;
+        debug!("Query matched {} recordings", entries.len());
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorConfig, KRecFrame, KRecHeader};
+
+    fn write_recording(path: &Path, task: &str, frames: u64) {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: format!("{}-uuid", task),
+            task: task.to_string(),
+            robot_platform: "kbot".to_string(),
+            actuator_configs: vec![
+                ActuatorConfig {
+                    actuator_id: 3,
+                    ..Default::default()
+                },
+                ActuatorConfig {
+                    actuator_id: 7,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        for i in 0..frames {
+            krec.add_frame(KRecFrame {
+                real_timestamp: i,
+                ..Default::default()
+            });
+        }
+        krec.save(path.to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn index_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        write_recording(&dir.path().join("walk.krec"), "walk", 3);
+        write_recording(&dir.path().join("nested/pick.krec"), "pick", 5);
+        fs::write(dir.path().join("broken.krec"), b"not a recording").unwrap();
+
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let summary = catalog.index_dir(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            summary,
+            IndexSummary {
+                indexed: 2,
+                failed: 1,
+                ..Default::default()
+            }
+        );
+
+        let entries = catalog.query("task = 'walk'").unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        let root = fs::canonicalize(dir.path()).unwrap();
+        assert_eq!(entry.path, root.join("walk.krec").to_string_lossy());
+        assert_eq!(entry.uuid, "walk-uuid");
+        assert_eq!(entry.robot_platform, "kbot");
+        assert_eq!(entry.frame_count, 3);
+        assert_eq!(entry.actuator_ids, vec![3, 7]);
+        assert_eq!(catalog.get(&entry.path).unwrap().as_ref(), Some(entry));
+
+        assert_eq!(catalog.query("frame_count > 3").unwrap()[0].task, "pick");
+        assert_eq!(catalog.query("").unwrap().len(), 2);
+        assert!(catalog.query("no_such_column = 1").is_err());
+    }
+
+    #[test]
+    fn reindexing_skips_unchanged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write_recording(&dir.path().join("walk.krec"), "walk", 3);
+        write_recording(&dir.path().join("pick.krec"), "pick", 5);
+        let dir_str = dir.path().to_str().unwrap();
+
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        assert_eq!(catalog.index_dir(dir_str).unwrap().indexed, 2);
+        assert_eq!(
+            catalog.index_dir(dir_str).unwrap(),
+            IndexSummary {
+                unchanged: 2,
+                ..Default::default()
+            }
+        );
+
+        // A changed file is re-read and a deleted one is dropped
+        write_recording(&dir.path().join("walk.krec"), "walk", 10);
+        fs::remove_file(dir.path().join("pick.krec")).unwrap();
+        assert_eq!(
+            catalog.index_dir(dir_str).unwrap(),
+            IndexSummary {
+                indexed: 1,
+                removed: 1,
+                ..Default::default()
+            }
+        );
+        let entries = catalog.query("").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].frame_count, 10);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::proto::KRecHeader;
+use crate::table::{Value, WideLayout};
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use std::fs::File;
+use std::io::{Read, Write};
+use tracing::{debug, info, instrument};
+
+impl KRec {
+    /// Writes the recording as CSV in the wide layout (one row per frame).
+    ///
+    /// See [`WideLayout`] for column naming. Missing values are written as empty cells.
+    #[instrument(skip(self, writer))]
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let layout = WideLayout::new(&self.header, &self.frames);
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(layout.column_names())?;
+
+        for frame in &self.frames {
+            let row = layout.row(frame);
+            writer.write_record(
+                row.iter()
+                    .map(|value| value.map(|v| v.to_string()).unwrap_or_default()),
+            )?;
+        }
+        writer.flush()?;
+
+        debug!(
+            "Wrote {} CSV rows with {} columns",
+            self.frames.len(),
+            layout.columns.len()
+        );
+        Ok(())
+    }
+
+    /// Saves the recording as a CSV file in the wide layout.
+    #[instrument(skip(self))]
+    pub fn to_csv(&self, path: &str) -> Result<()> {
+        info!("Saving KRec as CSV to: {}", path);
+        self.write_csv(File::create(path)?)
+    }
+
+    /// Reads a wide-layout CSV, using `header` to resolve named actuator columns.
+    #[instrument(skip(header, reader))]
+    pub fn read_csv<R: Read>(header: KRecHeader, reader: R) -> Result<Self> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let names: Vec<String> = reader.headers()?.iter().map(String::from).collect();
+        let layout = WideLayout::from_column_names(&header, &names)?;
+
+        let mut krec = KRec::new(header);
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            let row = layout
+                .columns
+                .iter()
+                .zip(record.iter())
+                .map(|(column, cell)| {
+                    if cell.trim().is_empty() {
+                        return Ok(None);
+                    }
+                    Value::parse(cell, column.column_type())
+                        .map(Some)
+                        .map_err(|e| {
+                            eyre!(
+                                "Invalid value '{}' in row {}, column '{}': {}",
+                                cell,
+                                i + 1,
+                                layout.column_name(column),
+                                e
+                            )
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            krec.frames.push(layout.frame_from_row(&row)?);
+        }
+
+        debug!("Read {} frames from CSV", krec.frames.len());
+        Ok(krec)
+    }
+
+    /// Loads a wide-layout CSV file, using `header` to resolve named actuator columns.
+    #[instrument(skip(header))]
+    pub fn from_csv(path: &str, header: KRecHeader) -> Result<Self> {
+        info!("Loading KRec from CSV: {}", path);
+        Self::read_csv(header, File::open(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::proto::Vec3;
+    use crate::proto::{ActuatorCommand, ActuatorConfig, ActuatorState, ImuValues, KRecFrame};
+
+    fn sample() -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            actuator_configs: vec![
+                ActuatorConfig {
+                    actuator_id: 3,
+                    name: Some("left knee".to_string()),
+                    ..Default::default()
+                },
+                ActuatorConfig {
+                    actuator_id: 7,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        for i in 0..4u64 {
+            let mut frame = KRecFrame {
+                real_timestamp: 1_000_000 * i,
+                video_timestamp: 500 * i,
+                video_frame_number: i,
+                inference_step: i,
+                ..Default::default()
+            };
+            // Frame 2 has no actuator data at all
+            if i != 2 {
+                frame.actuator_states = vec![
+                    ActuatorState {
+                        actuator_id: 3,
+                        online: true,
+                        position: Some(i as f64 * 0.1),
+                        velocity: (i == 0).then_some(-1.5),
+                        ..Default::default()
+                    },
+                    ActuatorState {
+                        actuator_id: 7,
+                        online: i % 2 == 0,
+                        torque: Some(1.0 / 3.0),
+                        voltage: Some(24.5),
+                        ..Default::default()
+                    },
+                    // Not in the header's configs
+                    ActuatorState {
+                        actuator_id: 12,
+                        online: true,
+                        temperature: Some(40.25),
+                        ..Default::default()
+                    },
+                ];
+                frame.actuator_commands = vec![ActuatorCommand {
+                    actuator_id: 3,
+                    position: 1.5,
+                    velocity: 0.0,
+                    torque: 0.25,
+                }];
+            }
+            // Frame 3 has no IMU, frame 1 only an accelerometer
+            if i != 3 {
+                frame.imu_values = Some(ImuValues {
+                    accel: Some(Vec3 {
+                        x: 0.5,
+                        y: -9.81,
+                        z: i as f64,
+                    }),
+                    gyro: (i != 1).then_some(Vec3 {
+                        x: 0.1,
+                        y: 0.2,
+                        z: 0.3,
+                    }),
+                    ..Default::default()
+                });
+            }
+            krec.frames.push(frame);
+        }
+        krec
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let krec = sample();
+        let mut csv = Vec::new();
+        krec.write_csv(&mut csv).unwrap();
+
+        let text = String::from_utf8(csv.clone()).unwrap();
+        let columns: Vec<&str> = text.lines().next().unwrap().split(',').collect();
+        for column in [
+            "left_knee_position",
+            "left_knee_cmd_position",
+            "act_7_torque",
+            "act_12_temperature",
+            "imu_accel_x",
+            "imu_gyro_z",
+        ] {
+            assert!(columns.contains(&column), "missing column {}", column);
+        }
+
+        let loaded = KRec::read_csv(krec.header.clone(), csv.as_slice()).unwrap();
+        assert_eq!(loaded.header, krec.header);
+        assert_eq!(loaded.frames, krec.frames);
+    }
+
+    #[test]
+    fn read_csv_rejects_unknown_columns() {
+        let csv = "real_timestamp,right_knee_position\n0,1.0\n";
+        let err = KRec::read_csv(KRecHeader::default(), csv.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("right_knee_position"));
+    }
+}
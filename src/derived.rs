@@ -0,0 +1,420 @@
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use std::collections::BTreeSet;
+use std::f64::consts::PI;
+use tracing::{debug, info, instrument};
+
+/// Optional smoothing applied to positions before differentiation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Smoothing {
+    #[default]
+    None,
+    /// Local least-squares polynomial fit over `window` samples (odd), using the actual timestamps
+    SavitzkyGolay { window: usize, order: usize },
+    /// Zero-phase (forward-backward) first-order low-pass filter
+    LowPass { cutoff_hz: f64 },
+}
+
+impl Smoothing {
+    fn validate(&self) -> Result<()> {
+        match *self {
+            Smoothing::None => Ok(()),
+            Smoothing::SavitzkyGolay { window, order } => {
+                if window % 2 != 1 || window < 3 {
+                    return Err(eyre!(
+                        "Savitzky-Golay window must be odd and at least 3, got {}",
+                        window
+                    ));
+                }
+                if order >= window {
+                    return Err(eyre!(
+                        "Savitzky-Golay order ({}) must be less than the window ({})",
+                        order,
+                        window
+                    ));
+                }
+                Ok(())
+            }
+            Smoothing::LowPass { cutoff_hz } => {
+                if !cutoff_hz.is_finite() || cutoff_hz <= 0.0 {
+                    return Err(eyre!(
+                        "Low-pass cutoff must be positive, got {} Hz",
+                        cutoff_hz
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn apply(&self, times: &[f64], values: &[f64]) -> Result<Vec<f64>> {
+        match *self {
+            Smoothing::None => Ok(values.to_vec()),
+            Smoothing::SavitzkyGolay { window, order } => {
+                savitzky_golay(times, values, window, order)
+            }
+            Smoothing::LowPass { cutoff_hz } => Ok(low_pass(times, values, cutoff_hz)),
+        }
+    }
+}
+
+/// Velocity, acceleration and jerk of one actuator, aligned with the recording's frames.
+///
+/// Values are `None` for frames where the actuator is missing, offline or has no position, and
+/// where a derivative cannot be computed (e.g. single-sample segments).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedSignals {
+    pub actuator_id: u32,
+    pub velocity: Vec<Option<f64>>,
+    pub acceleration: Vec<Option<f64>>,
+    pub jerk: Vec<Option<f64>>,
+}
+
+/// Solves `a * x = b` in place using Gaussian elimination with partial pivoting.
+///
+/// Returns `None` if a pivot is negligible relative to the largest entry of `a`.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    let scale = a.iter().flatten().fold(0.0f64, |max, v| max.max(v.abs()));
+    if scale == 0.0 || !scale.is_finite() {
+        return None;
+    }
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 * scale {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot_value) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Median of the positive intervals between consecutive timestamps.
+fn median_interval(times: &[f64]) -> Option<f64> {
+    let mut intervals: Vec<f64> = times
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|dt| *dt > 0.0)
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    intervals.sort_by(f64::total_cmp);
+    Some(intervals[intervals.len() / 2])
+}
+
+fn savitzky_golay(times: &[f64], values: &[f64], window: usize, order: usize) -> Result<Vec<f64>> {
+    let n = values.len();
+    if n < window {
+        return Ok(values.to_vec());
+    }
+    // Time offsets are measured in sample intervals so the normal equations stay well
+    // conditioned at any sample rate
+    let interval = median_interval(times)
+        .ok_or_else(|| eyre!("Savitzky-Golay smoothing needs increasing timestamps"))?;
+    let half = window / 2;
+    (0..n)
+        .map(|i| {
+            let start = i.saturating_sub(half).min(n - window);
+            let terms = order + 1;
+            let mut ata = vec![vec![0.0; terms]; terms];
+            let mut atb = vec![0.0; terms];
+            for j in start..start + window {
+                let dt = (times[j] - times[i]) / interval;
+                let powers: Vec<f64> = (0..terms).map(|p| dt.powi(p as i32)).collect();
+                for r in 0..terms {
+                    for c in 0..terms {
+                        ata[r][c] += powers[r] * powers[c];
+                    }
+                    atb[r] += powers[r] * values[j];
+                }
+            }
+            solve(ata, atb).map(|coeffs| coeffs[0]).ok_or_else(|| {
+                eyre!(
+                    "Savitzky-Golay fit of order {} is singular at sample {}; \
+                     the window has too few distinct timestamps",
+                    order,
+                    i
+                )
+            })
+        })
+        .collect()
+}
+
+fn low_pass(times: &[f64], values: &[f64], cutoff_hz: f64) -> Vec<f64> {
+    let rc = 1.0 / (2.0 * PI * cutoff_hz);
+    let pass = |indices: &mut dyn Iterator<Item = usize>, input: &[f64]| {
+        let mut output = input.to_vec();
+        let mut prev: Option<usize> = None;
+        for i in indices {
+            if let Some(p) = prev {
+                let dt = (times[i] - times[p]).abs();
+                let alpha = dt / (rc + dt);
+                output[i] = output[p] + alpha * (input[i] - output[p]);
+            }
+            prev = Some(i);
+        }
+        output
+    };
+    let forward = pass(&mut (0..values.len()), values);
+    pass(&mut (0..values.len()).rev(), &forward)
+}
+
+/// Finite-difference derivative using central differences inside and one-sided at the ends.
+fn differentiate(times: &[f64], values: &[Option<f64>]) -> Vec<Option<f64>> {
+    let n = values.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = match (i.checked_sub(1), (i + 1 < n).then_some(i + 1)) {
+                (Some(prev), Some(next)) => (prev, next),
+                (None, Some(next)) => (i, next),
+                (Some(prev), None) => (prev, i),
+                (None, None) => return None,
+            };
+            let dt = times[b] - times[a];
+            if dt <= 0.0 {
+                return None;
+            }
+            Some((values[b]? - values[a]?) / dt)
+        })
+        .collect()
+}
+
+impl KRec {
+    /// Computes per-actuator velocity, acceleration and jerk from positions and `real_timestamp`.
+    ///
+    /// Frames where the actuator is offline or has no position split the signal into independent
+    /// segments, so derivatives never span a gap.
+    #[instrument(skip(self))]
+    pub fn derived_signals(&self, smoothing: &Smoothing) -> Result<Vec<DerivedSignals>> {
+        smoothing.validate()?;
+
+        let frame_count = self.frames.len();
+        let actuator_ids: BTreeSet<u32> = self
+            .frames
+            .iter()
+            .flat_map(|f| f.actuator_states.iter().map(|s| s.actuator_id))
+            .collect();
+        debug!(
+            "Deriving signals for {} actuators over {} frames",
+            actuator_ids.len(),
+            frame_count
+        );
+
+        // Relative to the first frame, so epoch timestamps keep sub-microsecond precision
+        let origin = self.frames.first().map_or(0, |f| f.real_timestamp);
+        let times: Vec<f64> = self
+            .frames
+            .iter()
+            .map(|f| (f.real_timestamp as i128 - origin as i128) as f64 / 1e9)
+            .collect();
+
+        actuator_ids
+            .into_iter()
+            .map(|actuator_id| {
+                let positions: Vec<Option<f64>> = self
+                    .frames
+                    .iter()
+                    .map(|f| {
+                        f.actuator_states
+                            .iter()
+                            .find(|s| s.actuator_id == actuator_id && s.online)
+                            .and_then(|s| s.position)
+                    })
+                    .collect();
+
+                let mut signals = DerivedSignals {
+                    actuator_id,
+                    velocity: vec![None; frame_count],
+                    acceleration: vec![None; frame_count],
+                    jerk: vec![None; frame_count],
+                };
+
+                let mut start = 0;
+                while start < frame_count {
+                    if positions[start].is_none() {
+                        start += 1;
+                        continue;
+                    }
+                    let end = (start..frame_count)
+                        .find(|&i| positions[i].is_none())
+                        .unwrap_or(frame_count);
+
+                    let segment_times = &times[start..end];
+                    let raw: Vec<f64> = positions[start..end].iter().flatten().copied().collect();
+                    let smoothed: Vec<Option<f64>> = smoothing
+                        .apply(segment_times, &raw)?
+                        .into_iter()
+                        .map(Some)
+                        .collect();
+                    let velocity = differentiate(segment_times, &smoothed);
+                    let acceleration = differentiate(segment_times, &velocity);
+                    let jerk = differentiate(segment_times, &acceleration);
+
+                    signals.velocity[start..end].copy_from_slice(&velocity);
+                    signals.acceleration[start..end].copy_from_slice(&acceleration);
+                    signals.jerk[start..end].copy_from_slice(&jerk);
+                    start = end;
+                }
+
+                Ok(signals)
+            })
+            .collect()
+    }
+
+    /// Fills `ActuatorState.velocity` where it is missing, using the derived velocity.
+    ///
+    /// Returns the number of states that were filled in.
+    #[instrument(skip(self))]
+    pub fn fill_missing_velocity(&mut self, smoothing: &Smoothing) -> Result<usize> {
+        let derived = self.derived_signals(smoothing)?;
+        let mut filled = 0;
+        for signals in &derived {
+            for (frame, velocity) in self.frames.iter_mut().zip(&signals.velocity) {
+                let Some(velocity) = velocity else {
+                    continue;
+                };
+                if let Some(state) = frame
+                    .actuator_states
+                    .iter_mut()
+                    .find(|s| s.actuator_id == signals.actuator_id && s.velocity.is_none())
+                {
+                    state.velocity = Some(*velocity);
+                    filled += 1;
+                }
+            }
+        }
+        info!("Filled {} missing velocities", filled);
+        Ok(filled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorState, KRecFrame, KRecHeader};
+
+    /// Actuator 1 at 100 Hz moving at 2 units/s, with the position jumping by 100 after frame 4.
+    /// Frame 4 has no position, frame 10 is offline and frame 11 is isolated by the gap.
+    fn recording_with_gaps() -> KRec {
+        let mut krec = KRec::new(KRecHeader::default());
+        for i in 0..12u64 {
+            let t = i as f64 * 0.01;
+            let offset = if i > 4 { 100.0 } else { 0.0 };
+            krec.add_frame(KRecFrame {
+                real_timestamp: 10_000_000 * i,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: i != 10,
+                    position: (i != 4).then_some(2.0 * t + offset),
+                    // Measured velocity on the even frames of the first segment
+                    velocity: (i < 4 && i % 2 == 0).then_some(5.0),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    fn assert_close(value: Option<f64>, expected: f64) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+    }
+
+    fn polynomial(t: f64, order: usize) -> f64 {
+        (0..=order)
+            .map(|p| (p as f64 + 1.0) * t.powi(p as i32))
+            .sum()
+    }
+
+    #[test]
+    fn savitzky_golay_preserves_polynomials() {
+        for rate_hz in [100.0, 1000.0] {
+            for order in 2..=4 {
+                let times: Vec<f64> = (0..50).map(|i| i as f64 / rate_hz).collect();
+                let values: Vec<f64> = times.iter().map(|&t| polynomial(t, order)).collect();
+                let smoothed = savitzky_golay(&times, &values, 9, order).unwrap();
+                for (i, (value, expected)) in smoothed.iter().zip(&values).enumerate() {
+                    assert!(
+                        (value - expected).abs() < 1e-9,
+                        "{} Hz, order {}, sample {}: {} != {}",
+                        rate_hz,
+                        order,
+                        i,
+                        value,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn savitzky_golay_rejects_singular_windows() {
+        let times = [0.0, 0.0, 0.0, 0.001, 0.001, 0.001, 0.001];
+        let values = [1.0; 7];
+        assert!(savitzky_golay(&times, &values, 5, 3).is_err());
+        assert!(savitzky_golay(&[0.0; 5], &[1.0; 5], 5, 2).is_err());
+    }
+
+    #[test]
+    fn gaps_split_segments() {
+        let krec = recording_with_gaps();
+        for smoothing in [
+            Smoothing::None,
+            Smoothing::SavitzkyGolay {
+                window: 3,
+                order: 1,
+            },
+        ] {
+            let signals = &krec.derived_signals(&smoothing).unwrap()[0];
+            // Neither differentiation nor smoothing crosses the jump at frame 4
+            for i in (0..4).chain(5..10) {
+                assert_close(signals.velocity[i], 2.0);
+                assert_close(signals.acceleration[i], 0.0);
+            }
+            for i in [4, 10, 11] {
+                assert_eq!(signals.velocity[i], None, "{:?}, frame {}", smoothing, i);
+                assert_eq!(signals.acceleration[i], None);
+                assert_eq!(signals.jerk[i], None);
+            }
+        }
+    }
+
+    #[test]
+    fn fill_missing_velocity_keeps_measured_values() {
+        let mut krec = recording_with_gaps();
+        let filled = krec.fill_missing_velocity(&Smoothing::None).unwrap();
+        // Frames 1, 3 and 5..=9; frame 4 has no position and frames 10 and 11 no derivative
+        assert_eq!(filled, 7);
+
+        let velocities: Vec<Option<f64>> = krec
+            .frames
+            .iter()
+            .map(|f| f.actuator_states[0].velocity)
+            .collect();
+        assert_eq!(velocities[0], Some(5.0));
+        assert_eq!(velocities[2], Some(5.0));
+        for i in [1, 3, 5, 6, 7, 8, 9] {
+            assert_close(velocities[i], 2.0);
+        }
+        for i in [4, 10, 11] {
+            assert_eq!(velocities[i], None);
+        }
+    }
+}
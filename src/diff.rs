@@ -0,0 +1,410 @@
+use crate::proto::{ActuatorConfig, KRecHeader};
+use crate::table::{ActuatorField, Column, FrameField, ImuField, WideLayout};
+use crate::units::AngularField;
+use crate::KRec;
+use std::fmt;
+use tracing::{debug, instrument};
+
+/// A header field that differs between two recordings.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HeaderDifference {
+    /// Field name, e.g. `task` or `actuator_configs[12].kp`
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// Differences in one wide-layout column over the compared frames.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldDifference {
+    /// Column name (see [`WideLayout`])
+    pub field: String,
+    /// Largest absolute difference between values present in both recordings
+    pub max_abs_diff: f64,
+    /// Frame with the largest difference
+    pub max_frame_index: usize,
+    /// Number of frames where the difference exceeds the tolerance
+    pub differing_frames: usize,
+    /// Number of frames where the value is present in only one recording
+    pub missing_frames: usize,
+}
+
+/// Result of [`KRec::diff`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct KRecDiff {
+    pub tolerance: f64,
+    pub header: Vec<HeaderDifference>,
+    pub left_frame_count: usize,
+    pub right_frame_count: usize,
+    /// First frame where any value differs by more than the tolerance or is present in only
+    /// one recording, or where one recording ends
+    pub first_divergent_frame: Option<usize>,
+    /// Columns with any difference, in layout order
+    pub fields: Vec<FieldDifference>,
+}
+
+impl KRecDiff {
+    /// Whether the recordings match within the tolerance.
+    pub fn is_identical(&self) -> bool {
+        self.header.is_empty() && self.first_divergent_frame.is_none()
+    }
+}
+
+fn config_differences(left: &KRecHeader, right: &KRecHeader) -> Vec<HeaderDifference> {
+    let find = |header: &KRecHeader, id: u32| -> Option<ActuatorConfig> {
+        header
+            .actuator_configs
+            .iter()
+            .find(|c| c.actuator_id == id)
+            .cloned()
+    };
+    let mut ids: Vec<u32> = left
+        .actuator_configs
+        .iter()
+        .chain(&right.actuator_configs)
+        .map(|c| c.actuator_id)
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut differences = Vec::new();
+    for id in ids {
+        let (a, b) = match (find(left, id), find(right, id)) {
+            (Some(a), Some(b)) => (a, b),
+            (a, b) => {
+                differences.push(HeaderDifference {
+                    field: format!("actuator_configs[{}]", id),
+                    left: if a.is_some() { "present" } else { "missing" }.to_string(),
+                    right: if b.is_some() { "present" } else { "missing" }.to_string(),
+                });
+                continue;
+            }
+        };
+        let fields = [
+            ("kp", format!("{:?}", a.kp), format!("{:?}", b.kp)),
+            ("kd", format!("{:?}", a.kd), format!("{:?}", b.kd)),
+            ("ki", format!("{:?}", a.ki), format!("{:?}", b.ki)),
+            (
+                "max_torque",
+                format!("{:?}", a.max_torque),
+                format!("{:?}", b.max_torque),
+            ),
+            ("name", format!("{:?}", a.name), format!("{:?}", b.name)),
+        ];
+        for (name, left, right) in fields {
+            if left != right {
+                differences.push(HeaderDifference {
+                    field: format!("actuator_configs[{}].{}", id, name),
+                    left,
+                    right,
+                });
+            }
+        }
+    }
+    differences
+}
+
+fn header_differences(left: &KRecHeader, right: &KRecHeader) -> Vec<HeaderDifference> {
+    let mut fields = vec![
+        ("uuid", left.uuid.clone(), right.uuid.clone()),
+        ("task", left.task.clone(), right.task.clone()),
+        (
+            "robot_platform",
+            left.robot_platform.clone(),
+            right.robot_platform.clone(),
+        ),
+        (
+            "robot_serial",
+            left.robot_serial.clone(),
+            right.robot_serial.clone(),
+        ),
+        (
+            "start_timestamp",
+            left.start_timestamp.to_string(),
+            right.start_timestamp.to_string(),
+        ),
+        (
+            "end_timestamp",
+            left.end_timestamp.to_string(),
+            right.end_timestamp.to_string(),
+        ),
+    ];
+    let units = AngularField::ALL.iter().map(|field| {
+        (
+            field.name(),
+            left.angular_unit(*field).to_string(),
+            right.angular_unit(*field).to_string(),
+        )
+    });
+    fields.extend(units);
+
+    let mut differences: Vec<HeaderDifference> = fields
+        .into_iter()
+        .filter(|(_, left, right)| left != right)
+        .map(|(field, left, right)| HeaderDifference {
+            field: field.to_string(),
+            left,
+            right,
+        })
+        .collect();
+    differences.extend(config_differences(left, right));
+    differences
+}
+
+impl KRec {
+    /// Compares this recording (left) against `other` (right).
+    ///
+    /// Header fields are compared exactly, with angular units compared after resolving
+    /// defaults. Frames are compared pairwise by index over every wide-layout column (frame
+    /// fields, every actuator field of every actuator in either recording, and IMU values);
+    /// numeric values within `tolerance` of each other are considered equal.
+    #[instrument(skip(self, other))]
+    pub fn diff(&self, other: &KRec, tolerance: f64) -> KRecDiff {
+        let mut actuator_ids = WideLayout::new(&self.header, &self.frames).actuator_ids;
+        for id in WideLayout::new(&other.header, &other.frames).actuator_ids {
+            if !actuator_ids.contains(&id) {
+                actuator_ids.push(id);
+            }
+        }
+        let layout = WideLayout::with_actuators(&self.header, actuator_ids.clone());
+
+        let mut columns: Vec<Column> = FrameField::ALL.into_iter().map(Column::Frame).collect();
+        for &actuator_id in &actuator_ids {
+            for field in ActuatorField::ALL {
+                columns.push(Column::Actuator { actuator_id, field });
+            }
+        }
+        columns.extend(ImuField::all().into_iter().map(Column::Imu));
+
+        let mut fields: Vec<FieldDifference> = columns
+            .iter()
+            .map(|column| FieldDifference {
+                field: layout.column_name(column),
+                max_abs_diff: 0.0,
+                max_frame_index: 0,
+                differing_frames: 0,
+                missing_frames: 0,
+            })
+            .collect();
+        let mut first_divergent_frame = None;
+
+        for (i, (left, right)) in self.frames.iter().zip(&other.frames).enumerate() {
+            let mut divergent = false;
+            for (column, field) in columns.iter().zip(fields.iter_mut()) {
+                match (layout.value(column, left), layout.value(column, right)) {
+                    (None, None) => {}
+                    (Some(a), Some(b)) => {
+                        let (a, b) = (a.as_f64(), b.as_f64());
+                        let diff = if a == b || (a.is_nan() && b.is_nan()) {
+                            0.0
+                        } else {
+                            (a - b).abs()
+                        };
+                        // NaN against a number always counts as different
+                        if diff.is_nan() || diff > field.max_abs_diff {
+                            field.max_abs_diff = diff;
+                            field.max_frame_index = i;
+                        }
+                        if diff.is_nan() || diff > tolerance {
+                            field.differing_frames += 1;
+                            divergent = true;
+                        }
+                    }
+                    _ => {
+                        field.missing_frames += 1;
+                        divergent = true;
+                    }
+                }
+            }
+            if divergent && first_divergent_frame.is_none() {
+                first_divergent_frame = Some(i);
+            }
+        }
+
+        let (left_frame_count, right_frame_count) = (self.frames.len(), other.frames.len());
+        if first_divergent_frame.is_none() && left_frame_count != right_frame_count {
+            first_divergent_frame = Some(left_frame_count.min(right_frame_count));
+        }
+
+        fields.retain(|f| f.max_abs_diff != 0.0 || f.missing_frames > 0);
+        let diff = KRecDiff {
+            tolerance,
+            header: header_differences(&self.header, &other.header),
+            left_frame_count,
+            right_frame_count,
+            first_divergent_frame,
+            fields,
+        };
+        debug!(
+            "Found {} header and {} field differences",
+            diff.header.len(),
+            diff.fields.len()
+        );
+        diff
+    }
+}
+
+impl fmt::Display for KRecDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for difference in &self.header {
+            writeln!(
+                f,
+                "header.{}: {:?} != {:?}",
+                difference.field, difference.left, difference.right
+            )?;
+        }
+        if self.left_frame_count != self.right_frame_count {
+            writeln!(
+                f,
+                "frame count: {} != {}",
+                self.left_frame_count, self.right_frame_count
+            )?;
+        }
+        if let Some(frame) = self.first_divergent_frame {
+            writeln!(f, "first divergent frame: {}", frame)?;
+        }
+        for field in &self.fields {
+            write!(
+                f,
+                "{}: max_abs_diff={} at frame {}, {} frames over tolerance",
+                field.field, field.max_abs_diff, field.max_frame_index, field.differing_frames
+            )?;
+            if field.missing_frames > 0 {
+                write!(f, ", missing in {} frames", field.missing_frames)?;
+            }
+            writeln!(f)?;
+        }
+        if self.is_identical() {
+            writeln!(f, "identical (tolerance {})", self.tolerance)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorState, KRecFrame};
+
+    fn recording(positions: &[Option<f64>]) -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            ..Default::default()
+        });
+        for (i, position) in positions.iter().enumerate() {
+            krec.frames.push(KRecFrame {
+                real_timestamp: i as u64 * 1_000,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: true,
+                    position: *position,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    fn field<'a>(diff: &'a KRecDiff, name: &str) -> &'a FieldDifference {
+        diff.fields
+            .iter()
+            .find(|f| f.field == name)
+            .unwrap_or_else(|| panic!("no difference in {}", name))
+    }
+
+    #[test]
+    fn diff_identical() {
+        let krec = recording(&[Some(0.0), Some(1.0), None]);
+        let diff = krec.diff(&krec.clone(), 0.0);
+        assert!(diff.is_identical());
+        assert!(diff.fields.is_empty());
+        assert!(diff.to_string().contains("identical"));
+    }
+
+    #[test]
+    fn diff_tolerance() {
+        let left = recording(&[Some(0.0), Some(1.0), Some(2.0)]);
+        let right = recording(&[Some(0.0), Some(1.001), Some(2.0)]);
+
+        // Differences within the tolerance are reported but do not diverge
+        let diff = left.diff(&right, 0.01);
+        assert!(diff.is_identical());
+        assert_eq!(diff.first_divergent_frame, None);
+        let position = field(&diff, "act_1_position");
+        assert!((position.max_abs_diff - 0.001).abs() < 1e-9);
+        assert_eq!(position.max_frame_index, 1);
+        assert_eq!(position.differing_frames, 0);
+
+        let diff = left.diff(&right, 0.0);
+        assert!(!diff.is_identical());
+        assert_eq!(diff.first_divergent_frame, Some(1));
+        assert_eq!(field(&diff, "act_1_position").differing_frames, 1);
+        assert_eq!(diff.fields.len(), 1);
+    }
+
+    #[test]
+    fn diff_nan() {
+        // NaN matches NaN
+        let left = recording(&[Some(0.0), Some(f64::NAN)]);
+        assert!(left.diff(&left.clone(), 0.0).is_identical());
+
+        // NaN against a number differs at any tolerance
+        let right = recording(&[Some(0.0), Some(1.0)]);
+        let diff = left.diff(&right, f64::INFINITY);
+        assert_eq!(diff.first_divergent_frame, Some(1));
+        let position = field(&diff, "act_1_position");
+        assert!(position.max_abs_diff.is_nan());
+        assert_eq!(position.max_frame_index, 1);
+        assert_eq!(position.differing_frames, 1);
+    }
+
+    #[test]
+    fn diff_missing_columns() {
+        let left = recording(&[Some(0.0), Some(1.0)]);
+        let mut right = recording(&[Some(0.0), None]);
+        right.frames[1].actuator_states.push(ActuatorState {
+            actuator_id: 2,
+            online: true,
+            ..Default::default()
+        });
+        right.header.actuator_configs.push(ActuatorConfig {
+            actuator_id: 2,
+            ..Default::default()
+        });
+
+        let diff = left.diff(&right, 0.0);
+        assert_eq!(diff.first_divergent_frame, Some(1));
+        let position = field(&diff, "act_1_position");
+        assert_eq!(position.missing_frames, 1);
+        assert_eq!(position.differing_frames, 0);
+        assert_eq!(field(&diff, "act_2_online").missing_frames, 1);
+        assert_eq!(
+            diff.header,
+            vec![HeaderDifference {
+                field: "actuator_configs[2]".to_string(),
+                left: "missing".to_string(),
+                right: "present".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_frame_count() {
+        let left = recording(&[Some(0.0), Some(1.0)]);
+        let right = recording(&[Some(0.0), Some(1.0), Some(2.0)]);
+        let diff = left.diff(&right, 0.0);
+        assert!(!diff.is_identical());
+        assert!(diff.fields.is_empty());
+        assert_eq!((diff.left_frame_count, diff.right_frame_count), (2, 3));
+        assert_eq!(diff.first_divergent_frame, Some(2));
+        assert!(diff.to_string().contains("frame count: 2 != 3"));
+
+        // An earlier difference takes precedence over the length mismatch
+        let right = recording(&[Some(5.0), Some(1.0), Some(2.0)]);
+        assert_eq!(left.diff(&right, 0.0).first_divergent_frame, Some(0));
+    }
+}
@@ -1,6 +1,8 @@
-use crate::KRec;
+use crate::{KRec, KRecHeader, KRecReader};
 use color_eyre::{eyre::eyre, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
 use tempfile::NamedTempFile;
 use thiserror::Error;
 use tracing::{debug, info, instrument, warn};
@@ -13,6 +15,29 @@ pub enum FFmpegError {
     InputNotFound(String),
 }
 
+/// ffmpeg executable set by [`set_ffmpeg_path`], or `None` to use `ffmpeg` from `PATH`.
+static FFMPEG_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the ffmpeg executable used by this module. `ffprobe` is then looked up in the same
+/// directory.
+pub fn set_ffmpeg_path(path: impl Into<PathBuf>) {
+    *FFMPEG_PATH.write().unwrap() = Some(path.into());
+}
+
+fn ffmpeg_command() -> Command {
+    match FFMPEG_PATH.read().unwrap().as_ref() {
+        Some(path) => Command::new(path),
+        None => Command::new("ffmpeg"),
+    }
+}
+
+fn ffprobe_command() -> Command {
+    match FFMPEG_PATH.read().unwrap().as_ref() {
+        Some(path) => Command::new(path.with_file_name("ffprobe")),
+        None => Command::new("ffprobe"),
+    }
+}
+
 #[instrument(skip(video_path, krec_path, output_path))]
 pub fn combine_with_video(
     video_path: impl AsRef<Path>,
@@ -52,7 +77,7 @@ pub fn combine_with_video(
         return Err(eyre!("KRec file missing robot serial"));
     }
 
-    let mut command = std::process::Command::new("ffmpeg");
+    let mut command = ffmpeg_command();
     command.args([
         "-y", // Add -y flag to automatically overwrite files
         "-i",
@@ -95,25 +120,251 @@ pub fn combine_with_video(
     }
 }
 
-pub fn extract_from_video(video_path: &str, verbose: Option<bool>) -> Result<KRec, FFmpegError> {
-    info!("Starting extract_from_video");
-
-    // Check if input file exists
+/// File name and mimetype tags of each of a video's attachments, in order.
+fn attachment_tags(video_path: &str) -> Result<Vec<(String, String)>, FFmpegError> {
     if !Path::new(video_path).exists() {
         return Err(FFmpegError::InputNotFound(video_path.to_string()));
     }
+    let output = ffprobe_command()
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "t",
+            "-show_entries",
+            "stream=index:stream_tags=filename,mimetype",
+            "-of",
+            "compact=p=0",
+            video_path,
+        ])
+        .output()
+        .map_err(|e| FFmpegError::FFmpeg(format!("Failed to execute ffprobe: {}", e)))?;
+    if !output.status.success() {
+        return Err(FFmpegError::FFmpeg(format!(
+            "ffprobe failed with status {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    // One line per attachment, e.g. `index=2|tag:filename=a.krec|tag:mimetype=...`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let attachments: Vec<(String, String)> = stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let tag = |key: &str| {
+                line.split('|')
+                    .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            (tag("tag:filename"), tag("tag:mimetype"))
+        })
+        .collect();
+    debug!("Attachments of {}: {:?}", video_path, attachments);
+    Ok(attachments)
+}
+
+/// Index, among a video's attachments, of the attached KRec.
+///
+/// This is the first attachment whose file name ends in `.krec`, else the first with the
+/// `application/octet-stream` mimetype set by [`combine_with_video`].
+fn krec_attachment(attachments: &[(String, String)]) -> Option<usize> {
+    attachments
+        .iter()
+        .position(|(filename, _)| filename.to_ascii_lowercase().ends_with(".krec"))
+        .or_else(|| {
+            attachments
+                .iter()
+                .position(|(_, mimetype)| mimetype == "application/octet-stream")
+        })
+}
+
+/// Replaces the KRec attached to a combined video in place, keeping every other stream and
+/// attachment.
+///
+/// The KRec attachment is found as in [`extract_krec_attachment`]; if there is none, the KRec
+/// is added. The video is remuxed without re-encoding into a temporary file in the same
+/// directory, which then replaces the original. The new KRec becomes the video's last attachment.
+#[instrument(skip(video_path, krec_path))]
+pub fn replace_attachment(
+    video_path: impl AsRef<Path>,
+    krec_path: impl AsRef<Path>,
+    verbose: Option<bool>,
+) -> Result<()> {
+    let video_path = video_path.as_ref();
+    info!("Replacing KRec attachment of: {}", video_path.display());
+    let header = KRecReader::open(
+        krec_path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| eyre!("Invalid KRec path: {}", krec_path.as_ref().display()))?,
+    )?
+    .header()
+    .clone();
+
+    let video_str = video_path
+        .to_str()
+        .ok_or_else(|| eyre!("Invalid video path: {}", video_path.display()))?;
+    let attachments = attachment_tags(video_str)?;
+    let existing = krec_attachment(&attachments);
+    if existing.is_none() {
+        warn!(
+            "{} has no KRec attachment, adding one",
+            video_path.display()
+        );
+    }
+    // Attachments kept from the input come first, so the new one follows them
+    let new_attachment = attachments.len() - usize::from(existing.is_some());
+
+    let extension = video_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mkv".to_string());
+    // ffmpeg picks the muxer from the extension, so the temporary file keeps it
+    let dir = video_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let temp = tempfile::Builder::new()
+        .prefix(".krec-replace-")
+        .suffix(&format!(".{}", extension))
+        .tempfile_in(dir)?;
+    let temp_path = temp.path().to_path_buf();
+
+    let mut command = ffmpeg_command();
+    command.args(["-y", "-i", video_str, "-map", "0"]);
+    if let Some(index) = existing {
+        command.args(["-map", &format!("-0:t:{}", index)]);
+    }
+    let metadata = format!("-metadata:s:t:{}", new_attachment);
+    command.args([
+        "-attach",
+        &krec_path.as_ref().to_string_lossy(),
+        &metadata,
+        "mimetype=application/octet-stream",
+        &metadata,
+        &format!("uuid={}", header.uuid),
+        &metadata,
+        &format!("task={}", header.task),
+        &metadata,
+        &format!("robot_platform={}", header.robot_platform),
+        &metadata,
+        &format!("robot_serial={}", header.robot_serial),
+        "-c",
+        "copy",
+        &temp_path.to_string_lossy(),
+    ]);
+
+    if !verbose.unwrap_or(false) {
+        command
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| eyre!("Failed to execute ffmpeg: {}", e))?;
+    if !status.success() {
+        let err = eyre!("FFmpeg command failed with status: {}", status);
+        warn!("{}", err);
+        return Err(err);
+    }
+
+    temp.as_file()
+        .set_permissions(std::fs::metadata(video_path)?.permissions())?;
+    temp.persist(video_path)?;
+    info!("Successfully replaced KRec attachment");
+    Ok(())
+}
+
+pub fn extract_from_video(video_path: &str, verbose: Option<bool>) -> Result<KRec, FFmpegError> {
+    info!("Starting extract_from_video");
 
     // Create a temporary file for FFmpeg output
     let temp_file = NamedTempFile::new()
         .map_err(|e| FFmpegError::FFmpeg(format!("Failed to create temporary file: {}", e)))?;
     let temp_path = temp_file.path().to_string_lossy().to_string();
 
+    extract_krec_attachment(video_path, &temp_path, verbose)?;
+
+    // Load the KRec from the temporary file
+    let krec = KRec::load(&temp_path).map_err(|e| {
+        FFmpegError::FFmpeg(format!("Failed to load KRec from temporary file: {}", e))
+    })?;
+
+    // The temporary file will be automatically deleted when temp_file goes out of scope
+    info!("Successfully extracted KRec from video");
+    Ok(krec)
+}
+
+/// Reads only the header of the KRec attached to a combined video.
+pub fn read_header_from_video(
+    video_path: &str,
+    verbose: Option<bool>,
+) -> Result<KRecHeader, FFmpegError> {
+    let temp_file = NamedTempFile::new()
+        .map_err(|e| FFmpegError::FFmpeg(format!("Failed to create temporary file: {}", e)))?;
+    let temp_path = temp_file.path().to_string_lossy().to_string();
+    extract_krec_attachment(video_path, &temp_path, verbose)?;
+    KRec::read_header(&temp_path)
+        .map_err(|e| FFmpegError::FFmpeg(format!("Failed to read KRec header: {}", e)))
+}
+
+/// Counts the frames of the KRec attached to a combined video without decoding them.
+pub fn frame_count_from_video(
+    video_path: &str,
+    verbose: Option<bool>,
+) -> Result<usize, FFmpegError> {
+    let temp_file = NamedTempFile::new()
+        .map_err(|e| FFmpegError::FFmpeg(format!("Failed to create temporary file: {}", e)))?;
+    let temp_path = temp_file.path().to_string_lossy().to_string();
+    extract_krec_attachment(video_path, &temp_path, verbose)?;
+    KRec::frame_count(&temp_path)
+        .map_err(|e| FFmpegError::FFmpeg(format!("Failed to count KRec frames: {}", e)))
+}
+
+/// Writes the KRec attached to a combined video to `output_path` without decoding it.
+///
+/// The KRec is the first attachment named `*.krec`, else the first with the
+/// `application/octet-stream` mimetype, else the first attachment. The attachments are listed
+/// with ffprobe; if that fails, the first attachment is used.
+pub fn extract_krec_attachment(
+    video_path: &str,
+    output_path: impl AsRef<Path>,
+    verbose: Option<bool>,
+) -> Result<(), FFmpegError> {
+    let attachment = match attachment_tags(video_path) {
+        Ok(attachments) => krec_attachment(&attachments).unwrap_or(0),
+        Err(FFmpegError::InputNotFound(path)) => return Err(FFmpegError::InputNotFound(path)),
+        Err(e) => {
+            warn!("Using the first attachment of {}: {}", video_path, e);
+            0
+        }
+    };
+    extract_attachment(video_path, attachment, output_path, verbose)
+}
+
+/// Writes the attachment with index `attachment` (among the video's attachments) to
+/// `output_path` without decoding it.
+pub fn extract_attachment(
+    video_path: &str,
+    attachment: usize,
+    output_path: impl AsRef<Path>,
+    verbose: Option<bool>,
+) -> Result<(), FFmpegError> {
+    // Check if input file exists
+    if !Path::new(video_path).exists() {
+        return Err(FFmpegError::InputNotFound(video_path.to_string()));
+    }
+
     // Construct ffmpeg command
-    let mut command = std::process::Command::new("ffmpeg");
+    let mut command = ffmpeg_command();
     command.args([
         "-y",
-        "-dump_attachment:t:0",
-        &temp_path,
+        &format!("-dump_attachment:t:{}", attachment),
+        &output_path.as_ref().to_string_lossy(),
         "-i",
         video_path,
         "-f",
@@ -141,12 +392,136 @@ pub fn extract_from_video(video_path: &str, verbose: Option<bool>) -> Result<KRe
         return Err(FFmpegError::FFmpeg(error_msg));
     }
 
-    // Load the KRec from the temporary file
-    let krec = KRec::load(&temp_path).map_err(|e| {
-        FFmpegError::FFmpeg(format!("Failed to load KRec from temporary file: {}", e))
-    })?;
+    Ok(())
+}
 
-    // The temporary file will be automatically deleted when temp_file goes out of scope
-    info!("Successfully extracted KRec from video");
-    Ok(krec)
+/// Properties of a video's first video stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoInfo {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    pub codec: String,
+    pub pix_fmt: String,
+}
+
+/// Reads the first video stream's properties using ffprobe.
+#[instrument(skip(video_path))]
+pub fn probe_video(video_path: impl AsRef<Path>) -> Result<VideoInfo> {
+    let output = ffprobe_command()
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height,r_frame_rate,codec_name,pix_fmt",
+            "-of",
+            "default=noprint_wrappers=1",
+            &video_path.as_ref().to_string_lossy(),
+        ])
+        .output()
+        .map_err(|e| eyre!("Failed to execute ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "ffprobe failed with status {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let field = |key: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .map(str::trim)
+            .ok_or_else(|| eyre!("ffprobe output is missing '{}'", key))
+    };
+    let fps = match field("r_frame_rate")?.split_once('/') {
+        Some((num, den)) => num.parse::<f64>()? / den.parse::<f64>()?,
+        None => field("r_frame_rate")?.parse()?,
+    };
+
+    let info = VideoInfo {
+        width: field("width")?.parse()?,
+        height: field("height")?.parse()?,
+        fps,
+        codec: field("codec_name")?.to_string(),
+        pix_fmt: field("pix_fmt")?.to_string(),
+    };
+    debug!("Probed video: {:?}", info);
+    Ok(info)
+}
+
+/// Counts the frames of the first video stream using ffprobe, without decoding it.
+#[instrument(skip(video_path))]
+pub fn count_video_frames(video_path: impl AsRef<Path>) -> Result<u64> {
+    let output = ffprobe_command()
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-count_packets",
+            "-show_entries",
+            "stream=nb_read_packets",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            &video_path.as_ref().to_string_lossy(),
+        ])
+        .output()
+        .map_err(|e| eyre!("Failed to execute ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "ffprobe failed with status {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let count = stdout
+        .trim()
+        .parse()
+        .map_err(|e| eyre!("Unexpected ffprobe frame count '{}': {}", stdout.trim(), e))?;
+    debug!("Counted {} video frames", count);
+    Ok(count)
+}
+
+/// Copies the first video stream of `input_path` into `output_path` without re-encoding,
+/// dropping attachments and other streams.
+#[instrument(skip(input_path, output_path))]
+pub fn copy_video_stream(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    verbose: Option<bool>,
+) -> Result<()> {
+    let mut command = ffmpeg_command();
+    command.args([
+        "-y",
+        "-i",
+        &input_path.as_ref().to_string_lossy(),
+        "-map",
+        "0:v:0",
+        "-c",
+        "copy",
+        &output_path.as_ref().to_string_lossy(),
+    ]);
+
+    if !verbose.unwrap_or(false) {
+        command
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| eyre!("Failed to execute ffmpeg: {}", e))?;
+    if !status.success() {
+        let err = eyre!("FFmpeg command failed with status: {}", status);
+        warn!("{}", err);
+        return Err(err);
+    }
+    Ok(())
 }
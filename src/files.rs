@@ -0,0 +1,80 @@
+use color_eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extensions of video files that may carry a KRec attachment, in lowercase.
+pub const VIDEO_EXTENSIONS: [&str; 5] = ["mkv", "mp4", "mov", "webm", "avi"];
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Whether `path` has a video extension, ignoring case.
+pub fn is_video_file(path: impl AsRef<Path>) -> bool {
+    has_extension(path.as_ref(), &VIDEO_EXTENSIONS)
+}
+
+/// Whether `path` is a `.krec` file or has a video extension, ignoring case.
+pub fn is_recording_file(path: impl AsRef<Path>) -> bool {
+    has_extension(path.as_ref(), &["krec"]) || is_video_file(path)
+}
+
+/// `.krec` files and videos under `dir`, recursively, sorted by path.
+///
+/// Files are matched by extension only, as in [`is_recording_file`]. Symbolic links to
+/// directories are not followed, so link cycles cannot loop forever.
+pub fn find_recordings(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else if is_recording_file(&path) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_recordings_ignores_case() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        for name in ["a.krec", "b.KREC", "c.MKV", "nested/d.mp4", "e.txt", "krec"] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let found: Vec<PathBuf> = find_recordings(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            found,
+            ["a.krec", "b.KREC", "c.MKV", "nested/d.mp4"].map(PathBuf::from)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_recordings_skips_directory_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("nested/a.krec"), b"").unwrap();
+        // A cycle back to the root
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("nested/loop")).unwrap();
+
+        let found = find_recordings(dir.path()).unwrap();
+        assert_eq!(found, vec![dir.path().join("nested/a.krec")]);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::proto::KRecFrame;
+use crate::KRec;
+use prost::Message;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// Incremental SHA-256 over the canonical encoding of a sequence of frames.
+///
+/// Each frame is re-encoded and hashed with its length prefix, as in a `.krec` file. The header
+/// is not included, so recordings with the same frames match even if their UUID or other
+/// metadata differ, and the re-encoding makes the hash independent of how the frames were
+/// originally serialized.
+#[derive(Clone, Default)]
+pub struct ContentHasher {
+    hasher: Sha256,
+    frames: usize,
+    buffer: Vec<u8>,
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, frame: &KRecFrame) {
+        self.buffer.clear();
+        frame
+            .encode(&mut self.buffer)
+            .expect("Vec<u8> grows to fit the encoded frame");
+        self.hasher.update((self.buffer.len() as u32).to_le_bytes());
+        self.hasher.update(&self.buffer);
+        self.frames += 1;
+    }
+
+    /// Number of frames hashed so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns the hash as a lowercase hex string.
+    pub fn finish(self) -> String {
+        let digest = self.hasher.finalize();
+        let mut hex = String::with_capacity(digest.len() * 2);
+        for byte in digest {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        hex
+    }
+}
+
+impl KRec {
+    /// SHA-256 of the canonical frame encoding, as a hex string (see [`ContentHasher`]).
+    pub fn content_hash(&self) -> String {
+        let mut hasher = ContentHasher::new();
+        for frame in &self.frames {
+            hasher.update(frame);
+        }
+        hasher.finish()
+    }
+}
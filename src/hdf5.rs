@@ -0,0 +1,473 @@
+use crate::arrays::FrameArrays;
+use crate::metadata::HEADER_METADATA_KEY;
+use crate::proto::{ActuatorConfig, KRecHeader};
+use crate::table::{ActuatorField, ImuField};
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use hdf5::types::VarLenUnicode;
+use hdf5::{File, Group};
+use std::collections::HashMap;
+use tracing::{debug, info, instrument};
+
+/// Dataset paths used when writing and reading HDF5 files.
+///
+/// The defaults follow the ACT/ALOHA layout, plus `/action_velocity` and `/action_torque` for the
+/// command components ALOHA has no place for; readers that only know ALOHA ignore them. When a
+/// file lacks them, commanded velocities and torques are read as 0.
+///
+/// Camera images are not part of a recording, so `/observations/images` is neither written nor
+/// read; setting [`Hdf5Layout::images`] is an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hdf5Layout {
+    /// Actuator positions, `frames × actuators`
+    pub qpos: String,
+    /// Actuator velocities, `frames × actuators`
+    pub qvel: String,
+    /// Actuator torques, `frames × actuators`
+    pub effort: String,
+    /// Commanded positions, `frames × actuators`
+    pub action: String,
+    /// Commanded velocities, `frames × actuators`
+    pub action_velocity: String,
+    /// Commanded torques, `frames × actuators`
+    pub action_torque: String,
+    /// Group holding `accel`, `gyro`, `mag` (`frames × 3`) and `quaternion` (`frames × 4`)
+    pub imu: String,
+    /// `real_timestamp` of each frame
+    pub timestamps: String,
+    /// Group of per-camera image datasets. Images live in the recording's video, not in the
+    /// KRec, so this must be `None`; decode the video to get them
+    pub images: Option<String>,
+    /// Frame rate assumed when reading a file without timestamps
+    pub fallback_rate_hz: f64,
+}
+
+impl Default for Hdf5Layout {
+    fn default() -> Self {
+        Self {
+            qpos: "/observations/qpos".to_string(),
+            qvel: "/observations/qvel".to_string(),
+            effort: "/observations/effort".to_string(),
+            action: "/action".to_string(),
+            action_velocity: "/action_velocity".to_string(),
+            action_torque: "/action_torque".to_string(),
+            imu: "/observations/imu".to_string(),
+            timestamps: "/observations/timestamps".to_string(),
+            images: None,
+            fallback_rate_hz: 50.0,
+        }
+    }
+}
+
+impl Hdf5Layout {
+    /// Fails if the layout asks for camera images, which recordings do not hold.
+    fn check_images(&self) -> Result<()> {
+        match &self.images {
+            Some(images) => Err(eyre!(
+                "Cannot map camera images to '{}': KRec recordings do not store images, \
+                 decode them from the recording's video instead",
+                images
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// IMU datasets in the IMU group, with the fields they hold.
+fn imu_datasets() -> [(&'static str, Vec<ImuField>); 4] {
+    let fields = ImuField::all();
+    [
+        ("accel", fields[0..3].to_vec()),
+        ("gyro", fields[3..6].to_vec()),
+        ("mag", fields[6..9].to_vec()),
+        ("quaternion", fields[9..13].to_vec()),
+    ]
+}
+
+/// Opens the parent group of a dataset path, creating missing groups, and returns it with the
+/// dataset name.
+fn parent_group(file: &File, path: &str) -> Result<(Group, String)> {
+    let path = path.trim_matches('/');
+    let (parents, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let mut group = file.group("/")?;
+    for part in parents.split('/').filter(|p| !p.is_empty()) {
+        group = if group.link_exists(part) {
+            group.group(part)?
+        } else {
+            group.create_group(part)?
+        };
+    }
+    Ok((group, name.to_string()))
+}
+
+fn write_matrix(file: &File, path: &str, data: &[f64], rows: usize, cols: usize) -> Result<()> {
+    let (group, name) = parent_group(file, path)?;
+    group
+        .new_dataset::<f64>()
+        .shape([rows, cols])
+        .create(name.as_str())?
+        .write_raw(data)?;
+    Ok(())
+}
+
+/// Reads a 2-D `f64` dataset, checking it has `rows` rows, and returns it with its width.
+fn read_matrix(file: &File, path: &str, rows: usize) -> Result<Option<(Vec<f64>, usize)>> {
+    if !file.link_exists(path) {
+        return Ok(None);
+    }
+    let dataset = file.dataset(path)?;
+    let shape = dataset.shape();
+    match shape[..] {
+        [r, c] if r == rows => Ok(Some((dataset.read_raw::<f64>()?, c))),
+        _ => Err(eyre!(
+            "Dataset '{}' has shape {:?}, expected ({}, n)",
+            path,
+            shape,
+            rows
+        )),
+    }
+}
+
+impl KRec {
+    /// Writes the recording as an HDF5 file in the given layout.
+    ///
+    /// Actuator columns follow [`KRec::actuator_ids`], which is also stored in the
+    /// `actuator_ids` root attribute. Header fields are stored as root attributes (see
+    /// [`KRecHeader::to_metadata`]). The IMU group is only written if any frame has IMU values.
+    #[instrument(skip(self))]
+    pub fn to_hdf5(&self, path: &str, layout: &Hdf5Layout) -> Result<()> {
+        info!("Saving KRec as HDF5 to: {}", path);
+        layout.check_images()?;
+        let file = File::create(path)?;
+        let rows = self.frames.len();
+        let ids = self.actuator_ids();
+        let cols = ids.len();
+
+        let timestamps = self.timestamps();
+        let (group, name) = parent_group(&file, &layout.timestamps)?;
+        group
+            .new_dataset::<u64>()
+            .shape([rows])
+            .create(name.as_str())?
+            .write_raw(&timestamps)?;
+
+        for (path, field) in [
+            (&layout.qpos, ActuatorField::Position),
+            (&layout.qvel, ActuatorField::Velocity),
+            (&layout.effort, ActuatorField::Torque),
+            (&layout.action, ActuatorField::CommandPosition),
+            (&layout.action_velocity, ActuatorField::CommandVelocity),
+            (&layout.action_torque, ActuatorField::CommandTorque),
+        ] {
+            write_matrix(&file, path, &self.actuator_array(field, &ids), rows, cols)?;
+        }
+
+        if self.frames.iter().any(|f| f.imu_values.is_some()) {
+            for (name, fields) in imu_datasets() {
+                let path = format!("{}/{}", layout.imu.trim_end_matches('/'), name);
+                let data = self.imu_array(&fields);
+                write_matrix(&file, &path, &data, rows, fields.len())?;
+            }
+        }
+
+        file.new_attr::<u32>()
+            .shape([cols])
+            .create("actuator_ids")?
+            .write_raw(&ids)?;
+        for (key, value) in self.header.to_metadata() {
+            file.new_attr::<VarLenUnicode>()
+                .create(key.as_str())?
+                .write_scalar(&value.parse::<VarLenUnicode>()?)?;
+        }
+
+        debug!("Wrote {} frames for {} actuators", rows, cols);
+        Ok(())
+    }
+
+    /// Loads an HDF5 file in the given layout.
+    ///
+    /// The header is read from the root attributes when present; otherwise a header is created
+    /// with one actuator config per column, using the `actuator_ids` attribute (or the column
+    /// index) as the ID. Missing timestamps are generated at `layout.fallback_rate_hz`.
+    #[instrument]
+    pub fn from_hdf5(path: &str, layout: &Hdf5Layout) -> Result<Self> {
+        info!("Loading KRec from HDF5: {}", path);
+        layout.check_images()?;
+        let file = File::open(path)?;
+
+        let qpos = file.dataset(&layout.qpos)?;
+        let (rows, cols) = match qpos.shape()[..] {
+            [r, c] => (r, c),
+            ref shape => {
+                return Err(eyre!(
+                    "Dataset '{}' has shape {:?}, expected 2 dimensions",
+                    layout.qpos,
+                    shape
+                ))
+            }
+        };
+
+        let attr_names = file.attr_names()?;
+        let mut header = if attr_names.iter().any(|n| n == HEADER_METADATA_KEY) {
+            let mut metadata = HashMap::new();
+            for name in attr_names.iter().filter(|n| n.starts_with("krec.")) {
+                let value: VarLenUnicode = file.attr(name)?.read_scalar()?;
+                metadata.insert(name.clone(), value.as_str().to_string());
+            }
+            KRecHeader::from_metadata(&metadata)?
+        } else {
+            KRecHeader::default()
+        };
+
+        let ids: Vec<u32> = if attr_names.iter().any(|n| n == "actuator_ids") {
+            file.attr("actuator_ids")?.read_raw::<u32>()?
+        } else {
+            (0..cols as u32).collect()
+        };
+        if ids.len() != cols {
+            return Err(eyre!(
+                "Found {} actuator IDs but '{}' has {} columns",
+                ids.len(),
+                layout.qpos,
+                cols
+            ));
+        }
+        // Keep the file's column order, adding configs for any actuators the header lacks
+        let mut configs: Vec<ActuatorConfig> = ids
+            .iter()
+            .map(|id| {
+                header
+                    .actuator_configs
+                    .iter()
+                    .find(|c| c.actuator_id == *id)
+                    .cloned()
+                    .unwrap_or(ActuatorConfig {
+                        actuator_id: *id,
+                        ..Default::default()
+                    })
+            })
+            .collect();
+        configs.extend(
+            header
+                .actuator_configs
+                .iter()
+                .filter(|c| !ids.contains(&c.actuator_id))
+                .cloned(),
+        );
+        let extra = configs.len() - cols;
+        header.actuator_configs = configs;
+
+        let timestamps: Vec<u64> = if file.link_exists(&layout.timestamps) {
+            file.dataset(&layout.timestamps)?.read_raw::<u64>()?
+        } else {
+            let step_ns = 1e9 / layout.fallback_rate_hz;
+            (0..rows)
+                .map(|i| (i as f64 * step_ns).round() as u64)
+                .collect()
+        };
+        if timestamps.len() != rows {
+            return Err(eyre!(
+                "Found {} timestamps for {} frames",
+                timestamps.len(),
+                rows
+            ));
+        }
+
+        // Pad actuator matrices with NaN columns for configs that are not in the file
+        let pad = |data: Vec<f64>| -> Vec<f64> {
+            (0..rows)
+                .flat_map(|row| {
+                    data[row * cols..(row + 1) * cols]
+                        .iter()
+                        .copied()
+                        .chain(std::iter::repeat(f64::NAN).take(extra))
+                        .collect::<Vec<f64>>()
+                })
+                .collect()
+        };
+        let actuator = |path: &str| -> Result<Option<Vec<f64>>> {
+            match read_matrix(&file, path, rows)? {
+                Some((_, width)) if width != cols => Err(eyre!(
+                    "Dataset '{}' has {} columns, expected {}",
+                    path,
+                    width,
+                    cols
+                )),
+                Some((data, _)) => Ok(Some(pad(data))),
+                None => Ok(None),
+            }
+        };
+        let positions = actuator(&layout.qpos)?;
+        let velocities = actuator(&layout.qvel)?;
+        let torques = actuator(&layout.effort)?;
+        let command_parts = [
+            actuator(&layout.action)?,
+            actuator(&layout.action_velocity)?,
+            actuator(&layout.action_torque)?,
+        ];
+        let commands = command_parts.iter().any(Option::is_some).then(|| {
+            (0..rows * (cols + extra))
+                .flat_map(|i| {
+                    command_parts
+                        .iter()
+                        .map(move |part| part.as_ref().map_or(f64::NAN, |data| data[i]))
+                })
+                .collect::<Vec<f64>>()
+        });
+
+        let mut imu = vec![f64::NAN; rows * 13];
+        let mut has_imu = false;
+        let mut offset = 0;
+        for (name, fields) in imu_datasets() {
+            let path = format!("{}/{}", layout.imu.trim_end_matches('/'), name);
+            if let Some((data, width)) = read_matrix(&file, &path, rows)? {
+                if width != fields.len() {
+                    return Err(eyre!(
+                        "Dataset '{}' has {} columns, expected {}",
+                        path,
+                        width,
+                        fields.len()
+                    ));
+                }
+                for (row, values) in data.chunks(width).enumerate() {
+                    imu[row * 13 + offset..row * 13 + offset + width].copy_from_slice(values);
+                }
+                has_imu = true;
+            }
+            offset += fields.len();
+        }
+
+        let arrays = FrameArrays {
+            timestamps: &timestamps,
+            positions: positions.as_deref(),
+            velocities: velocities.as_deref(),
+            torques: torques.as_deref(),
+            commands: commands.as_deref(),
+            imu: has_imu.then_some(imu.as_slice()),
+            imu_width: 13,
+        };
+        KRec::from_arrays(header, &arrays)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::proto::Vec3;
+    use crate::proto::{ActuatorCommand, ActuatorState, ImuValues, KRecFrame};
+    use std::path::Path;
+
+    fn sample() -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            task: "pick".to_string(),
+            actuator_configs: vec![
+                ActuatorConfig {
+                    actuator_id: 3,
+                    name: Some("left knee".to_string()),
+                    ..Default::default()
+                },
+                ActuatorConfig {
+                    actuator_id: 7,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        for i in 0..3u64 {
+            krec.add_frame(KRecFrame {
+                real_timestamp: 1_000_000 * i,
+                actuator_states: vec![
+                    ActuatorState {
+                        actuator_id: 3,
+                        online: true,
+                        position: Some(i as f64 * 0.5),
+                        velocity: Some(-1.5),
+                        torque: Some(0.25),
+                        ..Default::default()
+                    },
+                    ActuatorState {
+                        actuator_id: 7,
+                        online: true,
+                        position: Some(2.0),
+                        ..Default::default()
+                    },
+                ],
+                actuator_commands: vec![ActuatorCommand {
+                    actuator_id: 3,
+                    position: 1.5,
+                    velocity: -0.5,
+                    torque: 0.75,
+                }],
+                imu_values: Some(ImuValues {
+                    accel: Some(Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 9.75,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample.h5");
+        let path = path.to_str().unwrap();
+        let krec = sample();
+        let layout = Hdf5Layout::default();
+
+        krec.to_hdf5(path, &layout).unwrap();
+        let loaded = KRec::from_hdf5(path, &layout).unwrap();
+
+        assert_eq!(loaded.header.uuid, "test");
+        assert_eq!(loaded.header.task, "pick");
+        assert_eq!(loaded.header.actuator_configs, krec.header.actuator_configs);
+        assert_eq!(loaded.timestamps(), krec.timestamps());
+        for (loaded, frame) in loaded.frames.iter().zip(&krec.frames) {
+            assert_eq!(loaded.actuator_states, frame.actuator_states);
+            assert_eq!(loaded.actuator_commands, frame.actuator_commands);
+            assert_eq!(loaded.imu_values, frame.imu_values);
+        }
+    }
+
+    #[test]
+    fn missing_command_components_read_as_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aloha.h5");
+        let path = path.to_str().unwrap();
+        sample().to_hdf5(path, &Hdf5Layout::default()).unwrap();
+
+        // An ALOHA reader only knows `/action`
+        let layout = Hdf5Layout {
+            action_velocity: "/missing_velocity".to_string(),
+            action_torque: "/missing_torque".to_string(),
+            ..Default::default()
+        };
+        let loaded = KRec::from_hdf5(path, &layout).unwrap();
+        let command = &loaded.frames[0].actuator_commands[0];
+        assert_eq!(
+            (command.position, command.velocity, command.torque),
+            (1.5, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn images_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("images.h5");
+        let path = path.to_str().unwrap();
+        let layout = Hdf5Layout {
+            images: Some("/observations/images".to_string()),
+            ..Default::default()
+        };
+        let err = sample().to_hdf5(path, &layout).unwrap_err();
+        assert!(err.to_string().contains("do not store images"));
+        assert!(!Path::new(path).exists());
+        assert!(KRec::from_hdf5(path, &layout).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::proto::KRecHeader;
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use prost::Message;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+use tracing::{debug, info, instrument};
+
+impl KRecHeader {
+    /// Header fields that can be set with [`KRecHeader::set_field`].
+    pub const EDITABLE_FIELDS: [&'static str; 6] = [
+        "uuid",
+        "task",
+        "robot_platform",
+        "robot_serial",
+        "start_timestamp",
+        "end_timestamp",
+    ];
+
+    /// Sets a scalar header field from its textual value.
+    pub fn set_field(&mut self, name: &str, value: &str) -> Result<()> {
+        let parse_timestamp = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|e| eyre!("Invalid value '{}' for {}: {}", value, name, e))
+        };
+        match name {
+            "uuid" => self.uuid = value.to_string(),
+            "task" => self.task = value.to_string(),
+            "robot_platform" => self.robot_platform = value.to_string(),
+            "robot_serial" => self.robot_serial = value.to_string(),
+            "start_timestamp" => self.start_timestamp = parse_timestamp(value)?,
+            "end_timestamp" => self.end_timestamp = parse_timestamp(value)?,
+            _ => {
+                return Err(eyre!(
+                    "Unknown header field '{}', expected one of: {}",
+                    name,
+                    Self::EDITABLE_FIELDS.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl KRec {
+    /// Reads only the header of a `.krec` file, without reading any frames.
+    #[instrument]
+    pub fn read_header(path: &str) -> Result<KRecHeader> {
+        debug!("Reading KRec header from: {}", path);
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut len_bytes = [0u8; 4];
+        file.read_exact(&mut len_bytes)
+            .map_err(|e| eyre!("Failed to read header length: {}", e))?;
+        let header_len = u32::from_le_bytes(len_bytes);
+        if 4 + u64::from(header_len) > file_len {
+            return Err(eyre!(
+                "Incomplete header data: need {} bytes, file has {}",
+                header_len,
+                file_len - 4
+            ));
+        }
+        let mut header_bytes = vec![0u8; header_len as usize];
+        file.read_exact(&mut header_bytes)?;
+        Ok(KRecHeader::decode(header_bytes.as_slice())?)
+    }
+
+    /// Counts the frames of a `.krec` file by following the length prefixes, without reading
+    /// or decoding the frames themselves.
+    #[instrument]
+    pub fn frame_count(path: &str) -> Result<usize> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut len_bytes = [0u8; 4];
+        let mut pos = 0u64;
+        let mut messages = 0usize;
+        while pos < file_len {
+            if pos + 4 > file_len {
+                return Err(eyre!(
+                    "Trailing data: {} bytes remaining after position {}",
+                    file_len - pos,
+                    pos
+                ));
+            }
+            reader.read_exact(&mut len_bytes)?;
+            let len = u64::from(u32::from_le_bytes(len_bytes));
+            pos += 4;
+            if pos + len > file_len {
+                return Err(eyre!(
+                    "Incomplete data: at position {}, need {} bytes, have {} bytes remaining",
+                    pos,
+                    len,
+                    file_len - pos
+                ));
+            }
+            reader.seek_relative(len as i64)?;
+            pos += len;
+            messages += 1;
+        }
+        if messages == 0 {
+            return Err(eyre!("File is empty, expected a KRec header"));
+        }
+        // The first message is the header
+        debug!("Counted {} frames in {}", messages - 1, path);
+        Ok(messages - 1)
+    }
+
+    /// Replaces the header of a `.krec` file in place.
+    ///
+    /// Frames are copied byte for byte without being decoded. The new file is written next to
+    /// the original and then renamed over it, so the original is left untouched on failure.
+    #[instrument(skip(header))]
+    pub fn rewrite_header(path: &str, header: &KRecHeader) -> Result<()> {
+        info!("Rewriting KRec header of: {}", path);
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let mut reader = BufReader::new(file);
+        let mut len_bytes = [0u8; 4];
+        reader
+            .read_exact(&mut len_bytes)
+            .map_err(|e| eyre!("Failed to read header length: {}", e))?;
+        let old_len = u32::from_le_bytes(len_bytes);
+        if 4 + u64::from(old_len) > metadata.len() {
+            return Err(eyre!(
+                "Incomplete header data: need {} bytes, file has {}",
+                old_len,
+                metadata.len() - 4
+            ));
+        }
+        reader.seek(SeekFrom::Current(i64::from(old_len)))?;
+
+        let dir = Path::new(path)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut temp = NamedTempFile::new_in(dir)?;
+        {
+            let mut writer = BufWriter::new(temp.as_file_mut());
+            let header_bytes = header.encode_to_vec();
+            writer.write_all(&(header_bytes.len() as u32).to_le_bytes())?;
+            writer.write_all(&header_bytes)?;
+            let copied = io::copy(&mut reader, &mut writer)?;
+            writer.flush()?;
+            debug!(
+                "Replaced {} byte header with {} bytes, copied {} frame bytes",
+                old_len,
+                header_bytes.len(),
+                copied
+            );
+        }
+        temp.as_file().set_permissions(metadata.permissions())?;
+        temp.persist(path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorConfig, ActuatorState, KRecFrame};
+
+    #[test]
+    fn rewrite_header_keeps_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.krec").to_string_lossy().to_string();
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "before".to_string(),
+            task: "walk".to_string(),
+            ..Default::default()
+        });
+        for i in 0..5u64 {
+            krec.frames.push(KRecFrame {
+                real_timestamp: i * 1_000,
+                video_frame_number: i,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    position: Some(i as f64),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec.save(&path).unwrap();
+
+        // Both a longer and a shorter header than the one on disk
+        for task in ["a much longer task description than before", "x"] {
+            let mut header = krec.header.clone();
+            header.task = task.to_string();
+            header.actuator_configs = vec![ActuatorConfig {
+                actuator_id: 1,
+                name: Some("joint".to_string()),
+                ..Default::default()
+            }];
+            KRec::rewrite_header(&path, &header).unwrap();
+
+            let loaded = KRec::load(&path).unwrap();
+            assert_eq!(loaded.header, header);
+            assert_eq!(loaded.frames, krec.frames);
+            assert_eq!(KRec::frame_count(&path).unwrap(), krec.frames.len());
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::proto::{KRecFrame, KRecHeader};
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use tracing::{debug, info, instrument};
+
+#[derive(Serialize)]
+struct KRecJsonRef<'a> {
+    header: &'a KRecHeader,
+    frames: &'a [KRecFrame],
+}
+
+#[derive(Deserialize)]
+struct KRecJson {
+    header: KRecHeader,
+    #[serde(default)]
+    frames: Vec<KRecFrame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeaderLine<T> {
+    header: T,
+}
+
+impl KRec {
+    /// Writes the recording as a JSON object with `header` and `frames` members.
+    ///
+    /// Messages use the protobuf canonical JSON mapping with the field names from
+    /// `proto/krec.proto`: 64-bit integers are strings, enums are value names and fields with
+    /// default values are omitted.
+    #[instrument(skip(self, writer))]
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer(
+            writer,
+            &KRecJsonRef {
+                header: &self.header,
+                frames: &self.frames,
+            },
+        )?;
+        debug!("Wrote {} frames as JSON", self.frames.len());
+        Ok(())
+    }
+
+    /// Saves the recording as a JSON file.
+    #[instrument(skip(self))]
+    pub fn to_json(&self, path: &str) -> Result<()> {
+        info!("Saving KRec as JSON to: {}", path);
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_json(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a recording written by [`KRec::write_json`].
+    #[instrument(skip(reader))]
+    pub fn read_json<R: Read>(reader: R) -> Result<Self> {
+        let json: KRecJson = serde_json::from_reader(reader)?;
+        debug!("Read {} frames from JSON", json.frames.len());
+        Ok(KRec {
+            header: json.header,
+            frames: json.frames,
+        })
+    }
+
+    /// Loads a recording from a JSON file written by [`KRec::to_json`].
+    #[instrument]
+    pub fn from_json(path: &str) -> Result<Self> {
+        info!("Loading KRec from JSON: {}", path);
+        Self::read_json(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the recording as newline-delimited JSON: a `{"header": ...}` line followed by one
+    /// line per frame, using the same mapping as [`KRec::write_json`].
+    #[instrument(skip(self, writer))]
+    pub fn write_ndjson<W: Write>(&self, mut writer: W) -> Result<()> {
+        serde_json::to_writer(
+            &mut writer,
+            &HeaderLine {
+                header: &self.header,
+            },
+        )?;
+        writeln!(writer)?;
+        for frame in &self.frames {
+            serde_json::to_writer(&mut writer, frame)?;
+            writeln!(writer)?;
+        }
+        debug!("Wrote {} frames as NDJSON", self.frames.len());
+        Ok(())
+    }
+
+    /// Saves the recording as an NDJSON file.
+    #[instrument(skip(self))]
+    pub fn to_ndjson(&self, path: &str) -> Result<()> {
+        info!("Saving KRec as NDJSON to: {}", path);
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_ndjson(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads newline-delimited JSON written by [`KRec::write_ndjson`].
+    ///
+    /// The header line is optional; without it the recording gets a default header. Blank
+    /// lines are skipped.
+    #[instrument(skip(reader))]
+    pub fn read_ndjson<R: BufRead>(reader: R) -> Result<Self> {
+        let mut krec = KRec::new(KRecHeader::default());
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if krec.frames.is_empty() {
+                let value: serde_json::Value = serde_json::from_str(&line)
+                    .map_err(|e| eyre!("Invalid JSON on line {}: {}", i + 1, e))?;
+                if value.get("header").is_some() {
+                    let line: HeaderLine<KRecHeader> = serde_json::from_value(value)
+                        .map_err(|e| eyre!("Invalid header on line {}: {}", i + 1, e))?;
+                    krec.header = line.header;
+                    continue;
+                }
+            }
+            krec.frames.push(
+                serde_json::from_str(&line)
+                    .map_err(|e| eyre!("Invalid frame on line {}: {}", i + 1, e))?,
+            );
+        }
+        debug!("Read {} frames from NDJSON", krec.frames.len());
+        Ok(krec)
+    }
+
+    /// Loads a recording from an NDJSON file written by [`KRec::to_ndjson`].
+    #[instrument]
+    pub fn from_ndjson(path: &str) -> Result<Self> {
+        info!("Loading KRec from NDJSON: {}", path);
+        Self::read_ndjson(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorCommand, ActuatorConfig, ActuatorState};
+
+    fn sample() -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            task: "walk".to_string(),
+            actuator_configs: vec![ActuatorConfig {
+                actuator_id: 3,
+                name: Some("left knee".to_string()),
+                kp: Some(0.0),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        for i in 0..3u64 {
+            krec.add_frame(KRecFrame {
+                real_timestamp: 1_000 * (i + 1),
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 3,
+                    online: true,
+                    // Set to zero, which must stay distinct from unset
+                    position: Some(0.0),
+                    torque: (i == 1).then_some(2.5),
+                    ..Default::default()
+                }],
+                actuator_commands: vec![ActuatorCommand {
+                    actuator_id: 3,
+                    position: 1.5,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    fn assert_same(loaded: &KRec, krec: &KRec) {
+        assert_eq!(loaded.header, krec.header);
+        assert_eq!(loaded.frames, krec.frames);
+        let state = &loaded.frames[0].actuator_states[0];
+        assert_eq!((state.position, state.velocity), (Some(0.0), None));
+        assert_eq!(loaded.header.actuator_configs[0].kp, Some(0.0));
+        assert_eq!(loaded.header.actuator_configs[0].kd, None);
+    }
+
+    fn assert_proto_field_names(json: &str) {
+        for key in [
+            "\"real_timestamp\"",
+            "\"actuator_states\"",
+            "\"actuator_id\"",
+        ] {
+            assert!(json.contains(key), "missing {} in {}", key, json);
+        }
+        for key in ["realTimestamp", "actuatorStates", "actuatorId"] {
+            assert!(!json.contains(key), "unexpected {} in {}", key, json);
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let krec = sample();
+        let mut buffer = Vec::new();
+        krec.write_json(&mut buffer).unwrap();
+        let json = String::from_utf8(buffer).unwrap();
+        assert_proto_field_names(&json);
+        assert!(json.contains("\"actuator_configs\""));
+
+        let loaded = KRec::read_json(json.as_bytes()).unwrap();
+        assert_same(&loaded, &krec);
+    }
+
+    #[test]
+    fn ndjson_round_trip() {
+        let krec = sample();
+        let mut buffer = Vec::new();
+        krec.write_ndjson(&mut buffer).unwrap();
+        let ndjson = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 1 + krec.frames.len());
+        assert!(lines[0].starts_with("{\"header\":"));
+        assert_proto_field_names(lines[1]);
+
+        let loaded = KRec::read_ndjson(ndjson.as_bytes()).unwrap();
+        assert_same(&loaded, &krec);
+    }
+
+    #[test]
+    fn ndjson_without_header() {
+        let krec = sample();
+        let mut buffer = Vec::new();
+        krec.write_ndjson(&mut buffer).unwrap();
+        let ndjson = String::from_utf8(buffer).unwrap();
+        let frames: String = ndjson
+            .lines()
+            .skip(1)
+            .map(|l| format!("{}\n\n", l))
+            .collect();
+
+        let loaded = KRec::read_ndjson(frames.as_bytes()).unwrap();
+        assert_eq!(loaded.header, KRecHeader::default());
+        assert_eq!(loaded.frames, krec.frames);
+    }
+}
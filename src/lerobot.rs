@@ -0,0 +1,503 @@
+use crate::ffmpeg::{
+    copy_video_stream, count_video_frames, extract_from_video, probe_video, VideoInfo,
+};
+use crate::resample::ResampleMethod;
+use crate::table::{ActuatorField, WideLayout};
+use crate::KRec;
+use arrow::array::{ArrayRef, FixedSizeListBuilder, Float32Array, Float32Builder, Int64Array};
+use arrow::record_batch::RecordBatch;
+use color_eyre::{eyre::eyre, Result};
+use parquet::arrow::ArrowWriter;
+use serde_json::{json, Value as JsonValue};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, info, instrument, warn};
+
+/// LeRobot dataset format version written to `meta/info.json`.
+const CODEBASE_VERSION: &str = "v2.0";
+
+/// Largest difference between an episode's video frame count and its resampled row count
+/// that is resolved by dropping the extra rows rather than failing.
+const VIDEO_FRAME_TOLERANCE: u64 = 2;
+
+const DATA_PATH: &str = "data/chunk-{episode_chunk:03d}/episode_{episode_index:06d}.parquet";
+const VIDEO_PATH: &str =
+    "videos/chunk-{episode_chunk:03d}/{video_key}/episode_{episode_index:06d}.mp4";
+
+/// Options for [`export_lerobot_dataset`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeRobotOptions {
+    /// Dataset frame rate. Defaults to the first video's frame rate, or the first recording's
+    /// mean frame rate when there is no video. Rounded to a whole number of frames per second.
+    pub fps: Option<f64>,
+    /// Value of `robot_type` in `meta/info.json`. Defaults to the first header's
+    /// `robot_platform`.
+    pub robot_type: Option<String>,
+    /// Feature key of the camera stream
+    pub video_key: String,
+    /// Number of episodes per chunk directory
+    pub chunks_size: usize,
+    /// Actuator fields concatenated into `observation.state`
+    pub state_fields: Vec<ActuatorField>,
+    /// Actuator fields concatenated into `action`
+    pub action_fields: Vec<ActuatorField>,
+    /// Show ffmpeg output
+    pub verbose: bool,
+}
+
+impl Default for LeRobotOptions {
+    fn default() -> Self {
+        Self {
+            fps: None,
+            robot_type: None,
+            video_key: "observation.images.main".to_string(),
+            chunks_size: 1000,
+            state_fields: vec![ActuatorField::Position],
+            action_fields: vec![ActuatorField::CommandPosition],
+            verbose: false,
+        }
+    }
+}
+
+/// A recording to export, with the video it was combined with.
+struct Episode {
+    krec: KRec,
+    video: Option<(String, VideoInfo)>,
+}
+
+fn load_episode(path: &Path, verbose: bool) -> Result<Episode> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| eyre!("Invalid input path: {}", path.display()))?;
+    let is_krec = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("krec"));
+    if is_krec {
+        return Ok(Episode {
+            krec: KRec::load(path_str)?,
+            video: None,
+        });
+    }
+
+    let krec = extract_from_video(path_str, Some(verbose))?;
+    let info = probe_video(path)?;
+    Ok(Episode {
+        krec,
+        video: Some((path_str.to_string(), info)),
+    })
+}
+
+/// Builds `frames × (fields × actuators)` values for the given fields, filling missing values
+/// with the previous frame's value, or the first value before it.
+///
+/// Fails if an actuator has no value at all for one of the fields.
+fn feature_matrix(krec: &KRec, fields: &[ActuatorField], ids: &[u32]) -> Result<Vec<f32>> {
+    let rows = krec.frames.len();
+    let width = fields.len() * ids.len();
+    let mut out = vec![0.0f32; rows * width];
+    for (f, field) in fields.iter().enumerate() {
+        let data = krec.actuator_array(*field, ids);
+        for (col, id) in ids.iter().enumerate() {
+            let first = (0..rows)
+                .map(|row| data[row * ids.len() + col])
+                .find(|value| !value.is_nan())
+                .ok_or_else(|| {
+                    eyre!(
+                        "Recording '{}' has no {} values for actuator {}",
+                        krec.header.uuid,
+                        field.name(),
+                        id
+                    )
+                })?;
+            let mut last = first as f32;
+            for row in 0..rows {
+                let value = data[row * ids.len() + col];
+                if !value.is_nan() {
+                    last = value as f32;
+                }
+                out[row * width + f * ids.len() + col] = last;
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn feature_names(layout: &WideLayout, fields: &[ActuatorField], ids: &[u32]) -> Vec<String> {
+    fields
+        .iter()
+        .flat_map(|field| {
+            ids.iter()
+                .map(move |id| format!("{}_{}", layout.prefix(*id), field.name()))
+        })
+        .collect()
+}
+
+fn fixed_size_list(values: &[f32], width: usize) -> ArrayRef {
+    let mut builder = FixedSizeListBuilder::new(Float32Builder::new(), width as i32);
+    for row in values.chunks(width) {
+        builder.values().append_slice(row);
+        builder.append(true);
+    }
+    Arc::new(builder.finish())
+}
+
+/// Per-dimension `mean`, `std`, `min` and `max` over all rows.
+fn feature_stats(matrices: &[Vec<f32>], width: usize) -> JsonValue {
+    let mut count = 0usize;
+    let mut sum = vec![0.0f64; width];
+    let mut sum_sq = vec![0.0f64; width];
+    let mut min = vec![f64::INFINITY; width];
+    let mut max = vec![f64::NEG_INFINITY; width];
+    for row in matrices.iter().flat_map(|m| m.chunks(width)) {
+        count += 1;
+        for (i, &value) in row.iter().enumerate() {
+            let value = value as f64;
+            sum[i] += value;
+            sum_sq[i] += value * value;
+            min[i] = min[i].min(value);
+            max[i] = max[i].max(value);
+        }
+    }
+    let n = count.max(1) as f64;
+    let mean: Vec<f64> = sum.iter().map(|s| s / n).collect();
+    let std: Vec<f64> = sum_sq
+        .iter()
+        .zip(&mean)
+        .map(|(sq, m)| (sq / n - m * m).max(0.0).sqrt())
+        .collect();
+    json!({ "mean": mean, "std": std, "min": min, "max": max })
+}
+
+fn write_json(path: &Path, value: &JsonValue) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn write_jsonl(path: &Path, values: &[JsonValue]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for value in values {
+        serde_json::to_writer(&mut writer, value)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes recordings as a LeRobot (v2.0) dataset directory, one episode per input.
+///
+/// Inputs are `.krec` files or videos combined with [`combine_with_video`]. Each recording is
+/// resampled onto a uniform grid at the dataset frame rate, starting at its first frame, which
+/// is assumed to line up with the first video frame. `options.state_fields` become
+/// `observation.state`, `options.action_fields` become `action` and `header.task` is the
+/// episode's task. Video streams are copied to `videos/` without re-encoding.
+///
+/// All recordings must have the same actuators, and either all or none of the inputs must be
+/// videos. Each video's frame count must be within two of its resampled episode's row count;
+/// extra rows are dropped so the episode is no longer than its video.
+/// `meta/stats.json` covers the state and action features only.
+///
+/// [`combine_with_video`]: crate::combine_with_video
+#[instrument(skip(inputs, output_dir))]
+pub fn export_lerobot_dataset(
+    inputs: &[impl AsRef<Path>],
+    output_dir: impl AsRef<Path>,
+    options: &LeRobotOptions,
+) -> Result<()> {
+    let output_dir = output_dir.as_ref();
+    info!(
+        "Exporting {} recordings as LeRobot dataset to: {}",
+        inputs.len(),
+        output_dir.display()
+    );
+    if inputs.is_empty() {
+        return Err(eyre!("No recordings to export"));
+    }
+    if options.chunks_size == 0 {
+        return Err(eyre!("chunks_size must be positive"));
+    }
+    if options.state_fields.is_empty() || options.action_fields.is_empty() {
+        return Err(eyre!("State and action fields must not be empty"));
+    }
+
+    let episodes = inputs
+        .iter()
+        .map(|path| load_episode(path.as_ref(), options.verbose))
+        .collect::<Result<Vec<_>>>()?;
+    let video = episodes[0].video.as_ref().map(|(_, info)| info.clone());
+    if episodes
+        .iter()
+        .any(|e| e.video.is_some() != video.is_some())
+    {
+        return Err(eyre!(
+            "Either all or none of the inputs must be videos with a KRec attachment"
+        ));
+    }
+
+    let first = &episodes[0].krec;
+    let ids = first.actuator_ids();
+    let layout = WideLayout::with_actuators(&first.header, ids.clone());
+    let fps = match (options.fps, &video) {
+        (Some(fps), _) => fps,
+        (None, Some(info)) => info.fps,
+        (None, None) => first
+            .stats()
+            .mean_rate_hz()
+            .ok_or_else(|| eyre!("Cannot infer frame rate from '{}'", first.header.uuid))?,
+    }
+    .round();
+    if !fps.is_finite() || fps < 1.0 {
+        return Err(eyre!("Frame rate must be at least 1 Hz, got {}", fps));
+    }
+    let robot_type = options
+        .robot_type
+        .clone()
+        .unwrap_or_else(|| first.header.robot_platform.clone());
+
+    let mut tasks: Vec<String> = Vec::new();
+    let mut episode_rows = Vec::new();
+    let mut states = Vec::new();
+    let mut actions = Vec::new();
+    let mut index = 0i64;
+    let state_width = options.state_fields.len() * ids.len();
+    let action_width = options.action_fields.len() * ids.len();
+
+    for (episode_index, episode) in episodes.iter().enumerate() {
+        if episode.krec.actuator_ids() != ids {
+            return Err(eyre!(
+                "Recording '{}' has actuators {:?}, expected {:?}",
+                episode.krec.header.uuid,
+                episode.krec.actuator_ids(),
+                ids
+            ));
+        }
+        let mut krec = episode.krec.resample(fps, ResampleMethod::Linear)?;
+        if krec.frames.is_empty() {
+            return Err(eyre!("Recording '{}' has no frames", krec.header.uuid));
+        }
+        // Checked before anything is written, so a mismatch leaves no partial episode behind
+        if let Some((video_path, _)) = &episode.video {
+            let video_frames = count_video_frames(video_path)?;
+            let rows = krec.frames.len() as u64;
+            if rows.abs_diff(video_frames) > VIDEO_FRAME_TOLERANCE {
+                return Err(eyre!(
+                    "Video '{}' has {} frames but episode {} has {} rows at {} fps",
+                    video_path,
+                    video_frames,
+                    episode_index,
+                    rows,
+                    fps
+                ));
+            }
+            if rows != video_frames {
+                warn!(
+                    "Video '{}' has {} frames but episode {} has {} rows; keeping {}",
+                    video_path,
+                    video_frames,
+                    episode_index,
+                    rows,
+                    rows.min(video_frames)
+                );
+                krec.frames.truncate(video_frames as usize);
+            }
+        }
+        let rows = krec.frames.len();
+
+        let task = krec.header.task.clone();
+        let task_index = match tasks.iter().position(|t| *t == task) {
+            Some(i) => i,
+            None => {
+                tasks.push(task.clone());
+                tasks.len() - 1
+            }
+        } as i64;
+
+        let state = feature_matrix(&krec, &options.state_fields, &ids)?;
+        let action = feature_matrix(&krec, &options.action_fields, &ids)?;
+        let batch = RecordBatch::try_from_iter([
+            ("observation.state", fixed_size_list(&state, state_width)),
+            ("action", fixed_size_list(&action, action_width)),
+            (
+                "timestamp",
+                Arc::new(Float32Array::from_iter_values(
+                    (0..rows).map(|i| (i as f64 / fps) as f32),
+                )) as ArrayRef,
+            ),
+            (
+                "frame_index",
+                Arc::new(Int64Array::from_iter_values(0..rows as i64)) as ArrayRef,
+            ),
+            (
+                "episode_index",
+                Arc::new(Int64Array::from_value(episode_index as i64, rows)) as ArrayRef,
+            ),
+            (
+                "index",
+                Arc::new(Int64Array::from_iter_values(index..index + rows as i64)) as ArrayRef,
+            ),
+            (
+                "task_index",
+                Arc::new(Int64Array::from_value(task_index, rows)) as ArrayRef,
+            ),
+        ])?;
+
+        let chunk = episode_index / options.chunks_size;
+        let data_path = output_dir.join(format!(
+            "data/chunk-{:03}/episode_{:06}.parquet",
+            chunk, episode_index
+        ));
+        fs::create_dir_all(data_path.parent().unwrap())?;
+        let mut writer = ArrowWriter::try_new(File::create(&data_path)?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        if let Some((video_path, info)) = &episode.video {
+            if (info.fps - fps).abs() > 1e-3 {
+                warn!(
+                    "Video '{}' is {} fps but the dataset is {} fps",
+                    video_path, info.fps, fps
+                );
+            }
+            let out = output_dir.join(format!(
+                "videos/chunk-{:03}/{}/episode_{:06}.mp4",
+                chunk, options.video_key, episode_index
+            ));
+            fs::create_dir_all(out.parent().unwrap())?;
+            copy_video_stream(video_path, &out, Some(options.verbose))?;
+        }
+
+        debug!("Wrote episode {} with {} frames", episode_index, rows);
+        episode_rows.push(json!({
+            "episode_index": episode_index,
+            "tasks": [task],
+            "length": rows,
+        }));
+        states.push(state);
+        actions.push(action);
+        index += rows as i64;
+    }
+
+    let vector_feature = |width: usize, names: Vec<String>| {
+        json!({
+            "dtype": "float32",
+            "shape": [width],
+            "names": { "motors": names },
+        })
+    };
+    let scalar_feature = |dtype: &str| json!({ "dtype": dtype, "shape": [1], "names": null });
+    let mut features = serde_json::Map::new();
+    features.insert(
+        "observation.state".to_string(),
+        vector_feature(
+            state_width,
+            feature_names(&layout, &options.state_fields, &ids),
+        ),
+    );
+    features.insert(
+        "action".to_string(),
+        vector_feature(
+            action_width,
+            feature_names(&layout, &options.action_fields, &ids),
+        ),
+    );
+    features.insert("timestamp".to_string(), scalar_feature("float32"));
+    for name in ["frame_index", "episode_index", "index", "task_index"] {
+        features.insert(name.to_string(), scalar_feature("int64"));
+    }
+    if let Some(info) = &video {
+        features.insert(
+            options.video_key.clone(),
+            json!({
+                "dtype": "video",
+                "shape": [info.height, info.width, 3],
+                "names": ["height", "width", "channel"],
+                "video_info": {
+                    "video.fps": info.fps,
+                    "video.codec": info.codec,
+                    "video.pix_fmt": info.pix_fmt,
+                    "video.is_depth_map": false,
+                    "has_audio": false,
+                },
+            }),
+        );
+    }
+
+    let total_episodes = episodes.len();
+    let info = json!({
+        "codebase_version": CODEBASE_VERSION,
+        "robot_type": robot_type,
+        "total_episodes": total_episodes,
+        "total_frames": index,
+        "total_tasks": tasks.len(),
+        "total_videos": if video.is_some() { total_episodes } else { 0 },
+        "total_chunks": total_episodes.div_ceil(options.chunks_size),
+        "chunks_size": options.chunks_size,
+        "fps": fps as u32,
+        "splits": { "train": format!("0:{}", total_episodes) },
+        "data_path": DATA_PATH,
+        "video_path": video.as_ref().map(|_| VIDEO_PATH),
+        "features": features,
+    });
+
+    let meta_dir = output_dir.join("meta");
+    fs::create_dir_all(&meta_dir)?;
+    write_json(&meta_dir.join("info.json"), &info)?;
+    write_jsonl(&meta_dir.join("episodes.jsonl"), &episode_rows)?;
+    let task_rows: Vec<JsonValue> = tasks
+        .iter()
+        .enumerate()
+        .map(|(task_index, task)| json!({ "task_index": task_index, "task": task }))
+        .collect();
+    write_jsonl(&meta_dir.join("tasks.jsonl"), &task_rows)?;
+    write_json(
+        &meta_dir.join("stats.json"),
+        &json!({
+            "observation.state": feature_stats(&states, state_width),
+            "action": feature_stats(&actions, action_width),
+        }),
+    )?;
+
+    info!(
+        "Exported {} episodes ({} frames) at {} fps",
+        total_episodes, index, fps
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorState, KRecFrame, KRecHeader};
+
+    fn recording(positions: &[Option<f64>]) -> KRec {
+        let mut krec = KRec::new(KRecHeader::default());
+        for (i, position) in positions.iter().enumerate() {
+            krec.frames.push(KRecFrame {
+                real_timestamp: i as u64,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: true,
+                    position: *position,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    #[test]
+    fn feature_matrix_fills_gaps_from_real_values() {
+        let krec = recording(&[None, None, Some(2.0), None, Some(3.0), None]);
+        let matrix = feature_matrix(&krec, &[ActuatorField::Position], &[1]).unwrap();
+        assert_eq!(matrix, vec![2.0, 2.0, 2.0, 2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn feature_matrix_rejects_missing_fields() {
+        let krec = recording(&[None, None]);
+        assert!(feature_matrix(&krec, &[ActuatorField::Position], &[1]).is_err());
+    }
+}
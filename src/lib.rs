@@ -13,13 +13,86 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+mod arrays;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "catalog")]
+mod catalog;
+mod csv;
+mod derived;
+mod diff;
 mod ffmpeg;
+mod files;
+mod hash;
+#[cfg(feature = "hdf5")]
+mod hdf5;
+mod header;
+#[cfg(feature = "serde")]
+mod json;
 mod krec;
+#[cfg(feature = "lerobot")]
+mod lerobot;
+#[cfg(feature = "mcap")]
+mod mcap;
+#[cfg(any(feature = "arrow", feature = "hdf5", feature = "mcap"))]
+mod metadata;
+#[cfg(feature = "parquet")]
+mod parquet;
 mod proto;
+mod reader;
+#[cfg(feature = "rerun")]
+mod rerun;
+mod resample;
+#[cfg(feature = "shards")]
+mod shards;
+mod stats;
+mod table;
+mod tracking;
+mod units;
+mod validate;
 
-pub use ffmpeg::{combine_with_video, extract_from_video};
+pub use arrays::FrameArrays;
+#[cfg(feature = "arrow")]
+pub use arrow::TableLayout;
+#[cfg(feature = "catalog")]
+pub use catalog::{Catalog, CatalogEntry, IndexSummary};
+pub use derived::{DerivedSignals, Smoothing};
+pub use diff::{FieldDifference, HeaderDifference, KRecDiff};
+pub use ffmpeg::{
+    combine_with_video, copy_video_stream, count_video_frames, extract_attachment,
+    extract_from_video, extract_krec_attachment, frame_count_from_video, probe_video,
+    read_header_from_video, replace_attachment, set_ffmpeg_path, VideoInfo,
+};
+pub use files::{find_recordings, is_recording_file, is_video_file, VIDEO_EXTENSIONS};
+pub use hash::ContentHasher;
+#[cfg(feature = "hdf5")]
+pub use hdf5::Hdf5Layout;
 pub use krec::KRec;
+#[cfg(feature = "lerobot")]
+pub use lerobot::{export_lerobot_dataset, LeRobotOptions};
+#[cfg(feature = "mcap")]
+pub use mcap::{MCAP_FRAME_TOPIC, MCAP_HEADER_METADATA};
+#[cfg(any(feature = "arrow", feature = "hdf5", feature = "mcap"))]
+pub use metadata::HEADER_METADATA_KEY;
 pub use proto::{
-    proto::Vec3, ActuatorCommand, ActuatorConfig, ActuatorState, ImuQuaternion, ImuValues,
-    KRecFrame, KRecHeader,
+    proto::{AngularUnit, UnitSystem, Vec3},
+    ActuatorCommand, ActuatorConfig, ActuatorState, ImuQuaternion, ImuValues, KRecFrame,
+    KRecHeader, FILE_DESCRIPTOR_SET,
+};
+pub use reader::KRecReader;
+pub use resample::ResampleMethod;
+#[cfg(feature = "shards")]
+pub use shards::{
+    read_shard_index, ShardEpisode, ShardIndex, ShardMember, ShardPiece, ShardWriter,
+    ShardedDataset, DEFAULT_SHARD_BYTES, SHARD_INDEX_NAME,
+};
+pub use stats::{
+    ActuatorStats, FieldStats, FrameGap, ImuStats, IntervalStats, KRecStats, Vec3Stats,
+};
+pub use table::{
+    ActuatorField, Axis, Column, ColumnType, FrameField, ImuField, QuaternionComponent, Value,
+    WideLayout,
 };
+pub use tracking::{ActuatorTracking, TrackingError, TrackingOptions};
+pub use units::AngularField;
+pub use validate::{Severity, ValidationIssue};
@@ -0,0 +1,179 @@
+use crate::proto::{KRecFrame, KRecHeader, FILE_DESCRIPTOR_SET};
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use mcap::read::LinearReader;
+use mcap::records::{MessageHeader, Metadata, Record};
+use mcap::{MessageStream, Writer};
+use prost::Message;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use tracing::{debug, info, instrument};
+
+/// MCAP topic carrying one `krec.proto.KRecFrame` message per frame.
+pub const MCAP_FRAME_TOPIC: &str = "/krec/frames";
+
+/// Name of the MCAP metadata record holding the header.
+pub const MCAP_HEADER_METADATA: &str = "krec_header";
+
+impl KRec {
+    /// Writes the recording as MCAP.
+    ///
+    /// Each frame is a protobuf `krec.proto.KRecFrame` message on [`MCAP_FRAME_TOPIC`], logged at
+    /// its `real_timestamp`, with `proto/krec.proto` as the schema. The header is stored in the
+    /// [`MCAP_HEADER_METADATA`] metadata record (see [`KRecHeader::to_metadata`]).
+    #[instrument(skip(self, writer))]
+    pub fn write_mcap<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let mut writer = Writer::new(writer)?;
+        let schema_id =
+            writer.add_schema("krec.proto.KRecFrame", "protobuf", FILE_DESCRIPTOR_SET)?;
+        let channel_id =
+            writer.add_channel(schema_id, MCAP_FRAME_TOPIC, "protobuf", &BTreeMap::new())?;
+
+        writer.write_metadata(&Metadata {
+            name: MCAP_HEADER_METADATA.to_string(),
+            metadata: self.header.to_metadata().into_iter().collect(),
+        })?;
+
+        for (sequence, frame) in self.frames.iter().enumerate() {
+            writer.write_to_known_channel(
+                &MessageHeader {
+                    channel_id,
+                    sequence: sequence as u32,
+                    log_time: frame.real_timestamp,
+                    publish_time: frame.real_timestamp,
+                },
+                &frame.encode_to_vec(),
+            )?;
+        }
+        writer.finish()?;
+
+        debug!("Wrote {} MCAP messages", self.frames.len());
+        Ok(())
+    }
+
+    /// Saves the recording as an MCAP file.
+    #[instrument(skip(self))]
+    pub fn to_mcap(&self, path: &str) -> Result<()> {
+        info!("Saving KRec as MCAP to: {}", path);
+        self.write_mcap(BufWriter::new(File::create(path)?))
+    }
+
+    /// Reads a recording written by [`KRec::write_mcap`] from an in-memory MCAP file.
+    ///
+    /// Messages on other topics are ignored.
+    #[instrument(skip(bytes))]
+    pub fn read_mcap(bytes: &[u8]) -> Result<Self> {
+        let mut header = None;
+        for record in LinearReader::new(bytes)? {
+            if let Record::Metadata(metadata) = record? {
+                if metadata.name == MCAP_HEADER_METADATA {
+                    let metadata: HashMap<String, String> = metadata.metadata.into_iter().collect();
+                    header = Some(KRecHeader::from_metadata(&metadata)?);
+                }
+            }
+        }
+        let header =
+            header.ok_or_else(|| eyre!("MCAP file has no '{}' metadata", MCAP_HEADER_METADATA))?;
+
+        let mut krec = KRec::new(header);
+        for message in MessageStream::new(bytes)? {
+            let message = message?;
+            if message.channel.topic == MCAP_FRAME_TOPIC {
+                krec.frames.push(KRecFrame::decode(message.data.as_ref())?);
+            }
+        }
+
+        debug!("Read {} frames from MCAP", krec.frames.len());
+        Ok(krec)
+    }
+
+    /// Loads a recording from an MCAP file written by [`KRec::to_mcap`].
+    #[instrument]
+    pub fn from_mcap(path: &str) -> Result<Self> {
+        info!("Loading KRec from MCAP: {}", path);
+        Self::read_mcap(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorCommand, ActuatorConfig, ActuatorState};
+    use std::io::Cursor;
+
+    fn sample() -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            task: "walk".to_string(),
+            start_timestamp: 1_000,
+            end_timestamp: 3_000,
+            actuator_configs: vec![ActuatorConfig {
+                actuator_id: 3,
+                name: Some("left knee".to_string()),
+                kp: Some(20.0),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        for i in 0..3u64 {
+            krec.add_frame(KRecFrame {
+                real_timestamp: 1_000 * (i + 1),
+                video_frame_number: i,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 3,
+                    online: true,
+                    position: Some(i as f64 * 0.5),
+                    ..Default::default()
+                }],
+                actuator_commands: vec![ActuatorCommand {
+                    actuator_id: 3,
+                    position: 1.5,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    #[test]
+    fn round_trip() {
+        let krec = sample();
+        let mut buffer = Cursor::new(Vec::new());
+        krec.write_mcap(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        let loaded = KRec::read_mcap(&bytes).unwrap();
+        assert_eq!(loaded.header, krec.header);
+        assert_eq!(loaded.frames, krec.frames);
+
+        let messages: Vec<_> = MessageStream::new(&bytes)
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(messages.len(), krec.frames.len());
+        for (message, frame) in messages.iter().zip(&krec.frames) {
+            assert_eq!(message.log_time, frame.real_timestamp);
+            let channel = &message.channel;
+            assert_eq!(channel.topic, MCAP_FRAME_TOPIC);
+            assert_eq!(channel.message_encoding, "protobuf");
+            let schema = channel.schema.as_ref().unwrap();
+            assert_eq!(schema.name, "krec.proto.KRecFrame");
+            assert_eq!(schema.encoding, "protobuf");
+            assert_eq!(schema.data.as_ref(), FILE_DESCRIPTOR_SET);
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample.mcap");
+        let path = path.to_str().unwrap();
+        let krec = sample();
+        krec.to_mcap(path).unwrap();
+        let loaded = KRec::from_mcap(path).unwrap();
+        assert_eq!(loaded.header, krec.header);
+        assert_eq!(loaded.frames, krec.frames);
+    }
+}
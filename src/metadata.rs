@@ -0,0 +1,49 @@
+use crate::proto::KRecHeader;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::{eyre::eyre, Result};
+use prost::Message;
+use std::collections::HashMap;
+
+/// Metadata key holding the base64-encoded protobuf `KRecHeader`.
+pub const HEADER_METADATA_KEY: &str = "krec.header";
+
+impl KRecHeader {
+    /// Header as string key-value metadata, for Arrow schemas, Parquet files and HDF5 attributes.
+    ///
+    /// The full header is stored under [`HEADER_METADATA_KEY`]; the most commonly queried
+    /// fields are also stored in plain text as `krec.<field>`.
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::from([
+            (
+                HEADER_METADATA_KEY.to_string(),
+                STANDARD.encode(self.encode_to_vec()),
+            ),
+            ("krec.uuid".to_string(), self.uuid.clone()),
+            ("krec.task".to_string(), self.task.clone()),
+            (
+                "krec.robot_platform".to_string(),
+                self.robot_platform.clone(),
+            ),
+            ("krec.robot_serial".to_string(), self.robot_serial.clone()),
+            (
+                "krec.start_timestamp".to_string(),
+                self.start_timestamp.to_string(),
+            ),
+            (
+                "krec.end_timestamp".to_string(),
+                self.end_timestamp.to_string(),
+            ),
+        ]);
+        metadata.retain(|_, value| !value.is_empty());
+        metadata
+    }
+
+    /// Decodes the header stored by [`KRecHeader::to_metadata`].
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Result<Self> {
+        let encoded = metadata
+            .get(HEADER_METADATA_KEY)
+            .ok_or_else(|| eyre!("Missing '{}' metadata", HEADER_METADATA_KEY))?;
+        let bytes = STANDARD.decode(encoded)?;
+        Ok(KRecHeader::decode(bytes.as_slice())?)
+    }
+}
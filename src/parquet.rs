@@ -0,0 +1,197 @@
+use crate::arrow::TableLayout;
+use crate::metadata::HEADER_METADATA_KEY;
+use crate::proto::KRecHeader;
+use crate::KRec;
+use arrow::record_batch::RecordBatch;
+use color_eyre::Result;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::ChunkReader;
+use parquet::format::KeyValue;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use tracing::{debug, info, instrument};
+
+impl KRec {
+    /// Writes the recording as a zstd-compressed Parquet file in the given layout.
+    ///
+    /// Header fields are stored in the file's key-value metadata (see
+    /// [`KRecHeader::to_metadata`](crate::KRecHeader::to_metadata)).
+    #[instrument(skip(self, writer))]
+    pub fn write_parquet<W: Write + Send>(&self, writer: W, layout: TableLayout) -> Result<()> {
+        self.write_parquet_with_compression(writer, layout, Compression::ZSTD(ZstdLevel::default()))
+    }
+
+    /// Writes the recording as a Parquet file in the given layout and compression.
+    #[instrument(skip(self, writer))]
+    pub fn write_parquet_with_compression<W: Write + Send>(
+        &self,
+        writer: W,
+        layout: TableLayout,
+        compression: Compression,
+    ) -> Result<()> {
+        let batch = self.to_record_batch(layout)?;
+
+        let mut metadata: Vec<KeyValue> = self
+            .header
+            .to_metadata()
+            .into_iter()
+            .map(|(key, value)| KeyValue::new(key, value))
+            .collect();
+        metadata.sort_by(|a, b| a.key.cmp(&b.key));
+        let props = WriterProperties::builder()
+            .set_compression(compression)
+            .set_key_value_metadata(Some(metadata))
+            .build();
+
+        let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        debug!("Wrote {} Parquet rows", batch.num_rows());
+        Ok(())
+    }
+
+    /// Saves the recording as a Parquet file in the given layout.
+    #[instrument(skip(self))]
+    pub fn to_parquet(&self, path: &str, layout: TableLayout) -> Result<()> {
+        info!("Saving KRec as Parquet to: {}", path);
+        self.write_parquet(File::create(path)?, layout)
+    }
+
+    /// Reads a Parquet file in either layout (see [`KRec::from_record_batches`]).
+    ///
+    /// The header is read from the file's key-value metadata when present; otherwise a
+    /// default header is used.
+    #[instrument(skip(reader))]
+    pub fn read_parquet<R: ChunkReader + 'static>(reader: R) -> Result<Self> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(reader)?;
+        let metadata: HashMap<String, String> = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .into_iter()
+            .flatten()
+            .filter_map(|kv| Some((kv.key.clone(), kv.value.clone()?)))
+            .collect();
+        let header = if metadata.contains_key(HEADER_METADATA_KEY) {
+            KRecHeader::from_metadata(&metadata)?
+        } else {
+            KRecHeader::default()
+        };
+
+        let batches = builder.build()?.collect::<Result<Vec<RecordBatch>, _>>()?;
+        debug!("Read {} Parquet record batches", batches.len());
+        KRec::from_record_batches(header, &batches)
+    }
+
+    /// Loads a Parquet file written by [`KRec::to_parquet`] or by other tools.
+    #[instrument]
+    pub fn from_parquet(path: &str) -> Result<Self> {
+        info!("Loading KRec from Parquet: {}", path);
+        Self::read_parquet(File::open(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorCommand, ActuatorConfig, ActuatorState, KRecFrame};
+
+    fn sample() -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: "test".to_string(),
+            task: "walk".to_string(),
+            actuator_configs: vec![
+                ActuatorConfig {
+                    actuator_id: 3,
+                    name: Some("left_knee".to_string()),
+                    kp: Some(20.0),
+                    ..Default::default()
+                },
+                ActuatorConfig {
+                    actuator_id: 7,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        for i in 0..4u64 {
+            krec.add_frame(KRecFrame {
+                real_timestamp: 1_000_000 * i,
+                video_frame_number: i,
+                actuator_states: vec![
+                    ActuatorState {
+                        actuator_id: 3,
+                        online: true,
+                        position: Some(i as f64 * 0.5),
+                        ..Default::default()
+                    },
+                    ActuatorState {
+                        actuator_id: 7,
+                        online: i % 2 == 0,
+                        torque: Some(-2.0),
+                        ..Default::default()
+                    },
+                ],
+                actuator_commands: vec![ActuatorCommand {
+                    actuator_id: 3,
+                    position: 1.5,
+                    velocity: 0.0,
+                    torque: 0.25,
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let krec = sample();
+        for layout in [TableLayout::Long, TableLayout::Wide] {
+            let path = dir.path().join(format!("{}.parquet", layout));
+            let path = path.to_str().unwrap();
+            krec.to_parquet(path, layout).unwrap();
+            let loaded = KRec::from_parquet(path).unwrap();
+            assert_eq!(loaded.header, krec.header, "{} layout", layout);
+            assert_eq!(loaded.frames, krec.frames, "{} layout", layout);
+        }
+    }
+
+    #[test]
+    fn uncompressed_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample.parquet");
+        let krec = sample();
+        krec.write_parquet_with_compression(
+            File::create(&path).unwrap(),
+            TableLayout::Wide,
+            Compression::UNCOMPRESSED,
+        )
+        .unwrap();
+        let loaded = KRec::from_parquet(path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.frames, krec.frames);
+    }
+
+    #[test]
+    fn missing_header_metadata_uses_default_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.parquet");
+        let krec = sample();
+        let batch = krec.to_record_batch(TableLayout::Long).unwrap();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let loaded = KRec::from_parquet(path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.header, KRecHeader::default());
+        assert_eq!(loaded.frames.len(), krec.frames.len());
+        assert_eq!(loaded.frames[1].actuator_states[0].position, Some(0.5));
+    }
+}
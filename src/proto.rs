@@ -1,7 +1,13 @@
 pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/krec.proto.rs"));
+    #[cfg(feature = "serde")]
+    include!(concat!(env!("OUT_DIR"), "/krec.proto.serde.rs"));
 }
 
+/// Serialized `FileDescriptorSet` for `proto/krec.proto`.
+pub const FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/krec_descriptor.bin"));
+
 pub use proto::{
     ActuatorCommand, ActuatorConfig, ActuatorState, ImuQuaternion, ImuValues, KRecFrame, KRecHeader,
 };
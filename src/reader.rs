@@ -0,0 +1,214 @@
+use crate::proto::{KRecFrame, KRecHeader};
+use color_eyre::{eyre::eyre, Result};
+use prost::Message;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use tracing::{debug, instrument};
+
+/// Reads a `.krec` stream one frame at a time, without loading the whole file.
+///
+/// The header is read when the reader is created. Frames are then returned by
+/// [`KRecReader::read_frame`] or by iterating over the reader.
+pub struct KRecReader<R> {
+    reader: R,
+    header: KRecHeader,
+    frames_read: usize,
+    buffer: Vec<u8>,
+}
+
+impl KRecReader<BufReader<File>> {
+    /// Opens a `.krec` file and reads its header.
+    #[instrument]
+    pub fn open(path: &str) -> Result<Self> {
+        debug!("Opening KRec stream: {}", path);
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> KRecReader<R> {
+    /// Reads the header from `reader`.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut buffer = Vec::new();
+        if !read_message(&mut reader, &mut buffer)? {
+            return Err(eyre!("Stream is empty, expected a KRec header"));
+        }
+        let header = KRecHeader::decode(buffer.as_slice())?;
+        Ok(Self {
+            reader,
+            header,
+            frames_read: 0,
+            buffer,
+        })
+    }
+
+    pub fn header(&self) -> &KRecHeader {
+        &self.header
+    }
+
+    /// Number of frames read or skipped so far.
+    pub fn frames_read(&self) -> usize {
+        self.frames_read
+    }
+
+    /// Reads the next frame, or returns `None` at the end of the stream.
+    pub fn read_frame(&mut self) -> Result<Option<KRecFrame>> {
+        if !read_message(&mut self.reader, &mut self.buffer)
+            .map_err(|e| eyre!("Frame {}: {}", self.frames_read, e))?
+        {
+            return Ok(None);
+        }
+        let frame = KRecFrame::decode(self.buffer.as_slice())?;
+        self.frames_read += 1;
+        Ok(Some(frame))
+    }
+
+    /// Skips the next frame without decoding it. Returns `false` at the end of the stream.
+    pub fn skip_frame(&mut self) -> Result<bool> {
+        let skipped = read_message(&mut self.reader, &mut self.buffer)
+            .map_err(|e| eyre!("Frame {}: {}", self.frames_read, e))?;
+        if skipped {
+            self.frames_read += 1;
+        }
+        Ok(skipped)
+    }
+}
+
+impl<R: Read> Iterator for KRecReader<R> {
+    type Item = Result<KRecFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+
+    /// Skips `n` frames without decoding them.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        for _ in 0..n {
+            match self.skip_frame() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.next()
+    }
+}
+
+/// Reads one length-prefixed message into `buffer`. Returns `false` if the stream ends
+/// cleanly before the length prefix.
+fn read_message<R: Read>(reader: &mut R, buffer: &mut Vec<u8>) -> Result<bool> {
+    let mut len_bytes = [0u8; 4];
+    let mut filled = 0;
+    while filled < len_bytes.len() {
+        match reader.read(&mut len_bytes[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(eyre!("Truncated length prefix: {} of 4 bytes", filled)),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    // The length is untrusted, so let the buffer grow with the data actually read
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    buffer.clear();
+    let read = reader.by_ref().take(len as u64).read_to_end(buffer)?;
+    if read < len {
+        return Err(eyre!("Incomplete data: need {} bytes, got {}", len, read));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(bytes: &mut Vec<u8>, message: &impl Message) {
+        let data = message.encode_to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+    }
+
+    fn stream(frames: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        message(
+            &mut bytes,
+            &KRecHeader {
+                uuid: "test".to_string(),
+                ..Default::default()
+            },
+        );
+        for i in 0..frames {
+            message(
+                &mut bytes,
+                &KRecFrame {
+                    real_timestamp: 1_000 * (i + 1),
+                    ..Default::default()
+                },
+            );
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_and_skips_frames() {
+        let bytes = stream(5);
+        let mut reader = KRecReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.header().uuid, "test");
+
+        assert_eq!(reader.next().unwrap().unwrap().real_timestamp, 1_000);
+        // Skips frames 1 and 2
+        assert_eq!(reader.nth(2).unwrap().unwrap().real_timestamp, 4_000);
+        assert_eq!(reader.frames_read(), 4);
+        assert!(reader.skip_frame().unwrap());
+        assert!(!reader.skip_frame().unwrap());
+        assert!(reader.next().is_none());
+        assert!(reader.nth(3).is_none());
+        assert_eq!(reader.frames_read(), 5);
+    }
+
+    #[test]
+    fn empty_stream() {
+        let err = KRecReader::new(&[][..]).err().unwrap();
+        assert!(err.to_string().contains("Stream is empty"));
+    }
+
+    #[test]
+    fn truncated_length_prefix() {
+        let mut bytes = stream(1);
+        bytes.extend_from_slice(&[7, 0]);
+        let mut reader = KRecReader::new(bytes.as_slice()).unwrap();
+        assert!(reader.read_frame().unwrap().is_some());
+        let err = reader.read_frame().unwrap_err().to_string();
+        assert!(err.contains("Frame 1"), "{}", err);
+        assert!(
+            err.contains("Truncated length prefix: 2 of 4 bytes"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let mut bytes = stream(2);
+        bytes.truncate(bytes.len() - 1);
+        let mut reader = KRecReader::new(bytes.as_slice()).unwrap();
+        assert!(reader.skip_frame().unwrap());
+        let err = reader.skip_frame().unwrap_err().to_string();
+        assert!(err.contains("Frame 1: Incomplete data"), "{}", err);
+        assert_eq!(reader.frames_read(), 1);
+    }
+
+    #[test]
+    fn oversized_length_is_an_error() {
+        let mut bytes = stream(0);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+        let mut reader = KRecReader::new(bytes.as_slice()).unwrap();
+        let err = reader.read_frame().unwrap_err().to_string();
+        assert!(
+            err.contains(&format!("need {} bytes, got 3", u32::MAX)),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::table::{ActuatorField, WideLayout};
+use crate::KRec;
+use color_eyre::Result;
+use rerun::{
+    Arrows3D, Quaternion, RecordingStream, RecordingStreamBuilder, Scalar, TextDocument,
+    Transform3D,
+};
+use tracing::{debug, info, instrument};
+
+/// Actuator fields logged as scalar time series.
+const SCALAR_FIELDS: [ActuatorField; 6] = [
+    ActuatorField::Position,
+    ActuatorField::Velocity,
+    ActuatorField::Torque,
+    ActuatorField::CommandPosition,
+    ActuatorField::CommandVelocity,
+    ActuatorField::CommandTorque,
+];
+
+impl KRec {
+    /// Logs the recording to a Rerun recording stream.
+    ///
+    /// Actuator values are logged as scalars under `actuators/<name>/<field>`, where `<name>` is
+    /// the column prefix from [`WideLayout`]. The IMU orientation is logged as a transform on
+    /// `imu`, and accel and gyro vectors as arrows on `imu/accel` and `imu/gyro`. Every frame is
+    /// placed on the `real_timestamp` and `video_frame_number` timelines.
+    #[instrument(skip(self, rec))]
+    pub fn log_to_rerun(&self, rec: &RecordingStream) -> Result<()> {
+        let layout = WideLayout::new(&self.header, &self.frames);
+        rec.log_static(
+            "header",
+            &TextDocument::new(format!(
+                "uuid: {}\ntask: {}\nrobot_platform: {}\nrobot_serial: {}",
+                self.header.uuid,
+                self.header.task,
+                self.header.robot_platform,
+                self.header.robot_serial
+            )),
+        )?;
+
+        for frame in &self.frames {
+            rec.set_time_nanos("real_timestamp", frame.real_timestamp as i64);
+            rec.set_time_sequence("video_frame_number", frame.video_frame_number as i64);
+
+            for &actuator_id in &layout.actuator_ids {
+                let prefix = layout.prefix(actuator_id);
+                for field in SCALAR_FIELDS {
+                    if let Some(value) = field.get(frame, actuator_id) {
+                        rec.log(
+                            format!("actuators/{}/{}", prefix, field.name()),
+                            &Scalar::new(value.as_f64()),
+                        )?;
+                    }
+                }
+            }
+
+            let Some(imu) = &frame.imu_values else {
+                continue;
+            };
+            if let Some(q) = &imu.quaternion {
+                rec.log(
+                    "imu",
+                    &Transform3D::from_rotation(Quaternion::from_xyzw([
+                        q.x as f32, q.y as f32, q.z as f32, q.w as f32,
+                    ])),
+                )?;
+            }
+            for (name, vector) in [("imu/accel", &imu.accel), ("imu/gyro", &imu.gyro)] {
+                if let Some(v) = vector {
+                    rec.log(
+                        name,
+                        &Arrows3D::from_vectors([[v.x as f32, v.y as f32, v.z as f32]]),
+                    )?;
+                }
+            }
+        }
+
+        debug!("Logged {} frames to Rerun", self.frames.len());
+        Ok(())
+    }
+
+    /// Writes the recording to a Rerun `.rrd` file.
+    #[instrument(skip(self))]
+    pub fn to_rerun(&self, path: &str) -> Result<()> {
+        info!("Saving KRec as Rerun recording to: {}", path);
+        let rec = RecordingStreamBuilder::new("krec").save(path)?;
+        self.log_to_rerun(&rec)?;
+        rec.flush_blocking();
+        Ok(())
+    }
+}
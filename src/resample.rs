@@ -0,0 +1,463 @@
+use crate::proto::{proto::Vec3, ActuatorState, ImuQuaternion, ImuValues, KRecFrame};
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{debug, info, instrument};
+
+/// How continuous signals are resampled onto the new time grid.
+///
+/// Discrete values (`online`, commands, frame numbers) always use the nearest frame, or the
+/// previous frame when `Hold` is selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResampleMethod {
+    /// Linear interpolation, with SLERP for quaternions
+    #[default]
+    Linear,
+    /// Value of the nearest frame in time
+    Nearest,
+    /// Value of the most recent frame at or before the sample time (zero-order hold)
+    Hold,
+}
+
+impl FromStr for ResampleMethod {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "linear" => Ok(Self::Linear),
+            "nearest" => Ok(Self::Nearest),
+            "hold" | "previous" => Ok(Self::Hold),
+            _ => Err(eyre!(
+                "Unknown resample method '{}', expected one of: linear, nearest, hold",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ResampleMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Nearest => write!(f, "nearest"),
+            Self::Hold => write!(f, "hold"),
+        }
+    }
+}
+
+/// Position of a grid sample between two source frames.
+#[derive(Debug, Clone, Copy)]
+struct Bracket {
+    before: usize,
+    after: usize,
+    /// Fraction of the way from `before` to `after`, in [0, 1]
+    alpha: f64,
+}
+
+impl Bracket {
+    /// Index of the frame used for discrete values.
+    fn discrete(&self, method: ResampleMethod) -> usize {
+        match method {
+            ResampleMethod::Hold => self.before,
+            _ if self.alpha < 0.5 => self.before,
+            _ => self.after,
+        }
+    }
+}
+
+fn lerp(a: f64, b: f64, alpha: f64) -> f64 {
+    a + (b - a) * alpha
+}
+
+fn lerp_opt(a: Option<f64>, b: Option<f64>, alpha: f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(lerp(a, b, alpha)),
+        _ if alpha < 0.5 => a,
+        _ => b,
+    }
+}
+
+fn lerp_vec3(a: Option<&Vec3>, b: Option<&Vec3>, alpha: f64) -> Option<Vec3> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Vec3 {
+            x: lerp(a.x, b.x, alpha),
+            y: lerp(a.y, b.y, alpha),
+            z: lerp(a.z, b.z, alpha),
+        }),
+        _ if alpha < 0.5 => a.cloned(),
+        _ => b.cloned(),
+    }
+}
+
+fn normalize(q: &ImuQuaternion) -> ImuQuaternion {
+    let norm = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+    if norm == 0.0 {
+        return q.clone();
+    }
+    ImuQuaternion {
+        x: q.x / norm,
+        y: q.y / norm,
+        z: q.z / norm,
+        w: q.w / norm,
+    }
+}
+
+/// Spherical linear interpolation between two orientations, taking the shortest path.
+fn slerp(a: &ImuQuaternion, b: &ImuQuaternion, alpha: f64) -> ImuQuaternion {
+    let a = normalize(a);
+    let mut b = normalize(b);
+    let mut dot = a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w;
+    if dot < 0.0 {
+        b = ImuQuaternion {
+            x: -b.x,
+            y: -b.y,
+            z: -b.z,
+            w: -b.w,
+        };
+        dot = -dot;
+    }
+
+    // Fall back to normalized linear interpolation for nearly identical orientations
+    if dot > 0.9995 {
+        return normalize(&ImuQuaternion {
+            x: lerp(a.x, b.x, alpha),
+            y: lerp(a.y, b.y, alpha),
+            z: lerp(a.z, b.z, alpha),
+            w: lerp(a.w, b.w, alpha),
+        });
+    }
+
+    let theta = dot.clamp(-1.0, 1.0).acos();
+    let sin_theta = theta.sin();
+    let wa = ((1.0 - alpha) * theta).sin() / sin_theta;
+    let wb = (alpha * theta).sin() / sin_theta;
+    ImuQuaternion {
+        x: wa * a.x + wb * b.x,
+        y: wa * a.y + wb * b.y,
+        z: wa * a.z + wb * b.z,
+        w: wa * a.w + wb * b.w,
+    }
+}
+
+fn resample_state(
+    before: Option<&ActuatorState>,
+    after: Option<&ActuatorState>,
+    bracket: &Bracket,
+    method: ResampleMethod,
+) -> Option<ActuatorState> {
+    let nearest = if bracket.discrete(method) == bracket.before {
+        before
+    } else {
+        after
+    };
+
+    match (method, before, after) {
+        // Only interpolate between two online samples; otherwise fall back to the nearest one
+        (ResampleMethod::Linear, Some(a), Some(b)) if a.online && b.online => {
+            let alpha = bracket.alpha;
+            Some(ActuatorState {
+                actuator_id: a.actuator_id,
+                online: true,
+                position: lerp_opt(a.position, b.position, alpha),
+                velocity: lerp_opt(a.velocity, b.velocity, alpha),
+                torque: lerp_opt(a.torque, b.torque, alpha),
+                temperature: lerp_opt(a.temperature, b.temperature, alpha),
+                voltage: lerp_opt(a.voltage.map(f64::from), b.voltage.map(f64::from), alpha)
+                    .map(|v| v as f32),
+                current: lerp_opt(a.current.map(f64::from), b.current.map(f64::from), alpha)
+                    .map(|v| v as f32),
+            })
+        }
+        _ => nearest.or(before).or(after).cloned(),
+    }
+}
+
+fn resample_imu(
+    before: Option<&ImuValues>,
+    after: Option<&ImuValues>,
+    bracket: &Bracket,
+    method: ResampleMethod,
+) -> Option<ImuValues> {
+    match (method, before, after) {
+        (ResampleMethod::Linear, Some(a), Some(b)) => {
+            let alpha = bracket.alpha;
+            Some(ImuValues {
+                accel: lerp_vec3(a.accel.as_ref(), b.accel.as_ref(), alpha),
+                gyro: lerp_vec3(a.gyro.as_ref(), b.gyro.as_ref(), alpha),
+                mag: lerp_vec3(a.mag.as_ref(), b.mag.as_ref(), alpha),
+                quaternion: match (&a.quaternion, &b.quaternion) {
+                    (Some(qa), Some(qb)) => Some(slerp(qa, qb, alpha)),
+                    _ if alpha < 0.5 => a.quaternion.clone(),
+                    _ => b.quaternion.clone(),
+                },
+            })
+        }
+        _ if bracket.discrete(method) == bracket.before => before.or(after).cloned(),
+        _ => after.or(before).cloned(),
+    }
+}
+
+fn resample_frame(
+    frames: &[KRecFrame],
+    timestamp: u64,
+    bracket: &Bracket,
+    method: ResampleMethod,
+) -> KRecFrame {
+    let before = &frames[bracket.before];
+    let after = &frames[bracket.after];
+    let discrete = &frames[bracket.discrete(method)];
+
+    let actuator_ids: BTreeSet<u32> = before
+        .actuator_states
+        .iter()
+        .chain(after.actuator_states.iter())
+        .map(|s| s.actuator_id)
+        .collect();
+    let actuator_states = actuator_ids
+        .into_iter()
+        .filter_map(|id| {
+            resample_state(
+                before.actuator_states.iter().find(|s| s.actuator_id == id),
+                after.actuator_states.iter().find(|s| s.actuator_id == id),
+                bracket,
+                method,
+            )
+        })
+        .collect();
+
+    let video_timestamp = match method {
+        ResampleMethod::Linear => lerp(
+            before.video_timestamp as f64,
+            after.video_timestamp as f64,
+            bracket.alpha,
+        )
+        .round() as u64,
+        _ => discrete.video_timestamp,
+    };
+
+    KRecFrame {
+        real_timestamp: timestamp,
+        video_timestamp,
+        video_frame_number: discrete.video_frame_number,
+        inference_step: discrete.inference_step,
+        actuator_states,
+        actuator_commands: discrete.actuator_commands.clone(),
+        imu_values: resample_imu(
+            before.imu_values.as_ref(),
+            after.imu_values.as_ref(),
+            bracket,
+            method,
+        ),
+    }
+}
+
+impl KRec {
+    /// Resamples the recording onto a uniform `real_timestamp` grid at `rate_hz`.
+    ///
+    /// The grid starts at the first frame and ends at or before the last frame. Positions,
+    /// velocities, torques and IMU vectors are interpolated according to `method`, orientations
+    /// use SLERP, and `online`, commands and frame numbers are taken from the nearest frame.
+    #[instrument(skip(self))]
+    pub fn resample(&self, rate_hz: f64, method: ResampleMethod) -> Result<KRec> {
+        if !rate_hz.is_finite() || rate_hz <= 0.0 {
+            return Err(eyre!("Resample rate must be positive, got {} Hz", rate_hz));
+        }
+        if self
+            .frames
+            .windows(2)
+            .any(|w| w[1].real_timestamp < w[0].real_timestamp)
+        {
+            return Err(eyre!(
+                "Cannot resample: real_timestamp must be non-decreasing across frames"
+            ));
+        }
+
+        let mut resampled = KRec::new(self.header.clone());
+        let (Some(first), Some(last)) = (self.frames.first(), self.frames.last()) else {
+            return Ok(resampled);
+        };
+
+        info!(
+            "Resampling {} frames to {} Hz using {} interpolation",
+            self.frames.len(),
+            rate_hz,
+            method
+        );
+        let step_ns = 1e9 / rate_hz;
+        let start = first.real_timestamp;
+        let span = (last.real_timestamp - start) as f64;
+        let sample_count = (span / step_ns).floor() as u64 + 1;
+
+        let mut index = 0;
+        for k in 0..sample_count {
+            let timestamp = start + (k as f64 * step_ns).round() as u64;
+            while index + 1 < self.frames.len()
+                && self.frames[index + 1].real_timestamp <= timestamp
+            {
+                index += 1;
+            }
+            let after = (index + 1).min(self.frames.len() - 1);
+            let t0 = self.frames[index].real_timestamp;
+            let t1 = self.frames[after].real_timestamp;
+            let alpha = if t1 > t0 {
+                (timestamp.saturating_sub(t0)) as f64 / (t1 - t0) as f64
+            } else {
+                0.0
+            };
+            let bracket = Bracket {
+                before: index,
+                after,
+                alpha: alpha.clamp(0.0, 1.0),
+            };
+            resampled
+                .frames
+                .push(resample_frame(&self.frames, timestamp, &bracket, method));
+        }
+
+        debug!("Resampled to {} frames", resampled.frames.len());
+        Ok(resampled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::KRecHeader;
+
+    const MS: u64 = 1_000_000;
+
+    /// Frames at 10 Hz whose position is the frame index.
+    fn ramp() -> KRec {
+        let mut krec = KRec::new(KRecHeader::default());
+        for i in 0..4u64 {
+            krec.frames.push(KRecFrame {
+                real_timestamp: i * 100 * MS,
+                video_frame_number: i,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: true,
+                    position: Some(i as f64),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    fn positions(krec: &KRec) -> Vec<f64> {
+        krec.frames
+            .iter()
+            .map(|f| f.actuator_states[0].position.unwrap())
+            .collect()
+    }
+
+    /// Rotation by `angle` radians about the z axis.
+    fn quaternion(angle: f64) -> ImuQuaternion {
+        ImuQuaternion {
+            x: 0.0,
+            y: 0.0,
+            z: (angle / 2.0).sin(),
+            w: (angle / 2.0).cos(),
+        }
+    }
+
+    fn assert_quaternion_eq(actual: &ImuQuaternion, expected: &ImuQuaternion) {
+        for (a, e) in [
+            (actual.x, expected.x),
+            (actual.y, expected.y),
+            (actual.z, expected.z),
+            (actual.w, expected.w),
+        ] {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn resample_ramp() {
+        let krec = ramp();
+        let timestamps: Vec<u64> = (0..13).map(|k| k * 25 * MS).collect();
+
+        let linear = krec.resample(40.0, ResampleMethod::Linear).unwrap();
+        assert_eq!(
+            linear
+                .frames
+                .iter()
+                .map(|f| f.real_timestamp)
+                .collect::<Vec<_>>(),
+            timestamps
+        );
+        for (position, timestamp) in positions(&linear).iter().zip(&timestamps) {
+            assert!((position - *timestamp as f64 / (100 * MS) as f64).abs() < 1e-12);
+        }
+
+        let nearest = krec.resample(40.0, ResampleMethod::Nearest).unwrap();
+        assert_eq!(
+            positions(&nearest),
+            [0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 3.0, 3.0, 3.0]
+        );
+
+        let hold = krec.resample(40.0, ResampleMethod::Hold).unwrap();
+        assert_eq!(
+            positions(&hold),
+            [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 3.0]
+        );
+        assert_eq!(
+            hold.frames
+                .iter()
+                .map(|f| f.video_frame_number)
+                .collect::<Vec<_>>(),
+            [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3]
+        );
+    }
+
+    #[test]
+    fn slerp_interpolates_rotation_angle() {
+        let a = quaternion(0.0);
+        let b = quaternion(std::f64::consts::FRAC_PI_2);
+        assert_quaternion_eq(&slerp(&a, &b, 0.0), &a);
+        assert_quaternion_eq(&slerp(&a, &b, 1.0), &b);
+        assert_quaternion_eq(
+            &slerp(&a, &b, 0.5),
+            &quaternion(std::f64::consts::FRAC_PI_4),
+        );
+
+        // The negated quaternion is the same orientation, so the shortest path is unchanged
+        let negated = ImuQuaternion {
+            x: -b.x,
+            y: -b.y,
+            z: -b.z,
+            w: -b.w,
+        };
+        assert_quaternion_eq(
+            &slerp(&a, &negated, 0.5),
+            &quaternion(std::f64::consts::FRAC_PI_4),
+        );
+    }
+
+    #[test]
+    fn slerp_falls_back_to_nlerp_for_close_orientations() {
+        // dot = cos(0.01 / 2) > 0.9995
+        let a = quaternion(0.0);
+        let b = quaternion(0.01);
+        let q = slerp(&a, &b, 0.25);
+        let norm = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+        assert!((norm - 1.0).abs() < 1e-12);
+        let expected = quaternion(0.0025);
+        assert!((q.z - expected.z).abs() < 1e-8 && (q.w - expected.w).abs() < 1e-8);
+
+        // Identical orientations must not divide by sin(0)
+        assert_quaternion_eq(&slerp(&b, &b, 0.5), &b);
+    }
+
+    #[test]
+    fn resample_rejects_decreasing_timestamps() {
+        let mut krec = ramp();
+        krec.frames.swap(1, 2);
+        let err = krec.resample(40.0, ResampleMethod::Linear).unwrap_err();
+        assert!(err.to_string().contains("non-decreasing"));
+        assert!(ramp().resample(0.0, ResampleMethod::Linear).is_err());
+    }
+}
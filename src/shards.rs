@@ -0,0 +1,617 @@
+use crate::proto::KRecHeader;
+use crate::{KRec, KRecReader};
+use color_eyre::{eyre::eyre, Result};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use tracing::{debug, info, instrument, warn};
+
+/// Default shard size limit used by the CLI and bindings, 1 GiB.
+pub const DEFAULT_SHARD_BYTES: u64 = 1 << 30;
+
+/// Name of the tar member holding the [`ShardIndex`], written last in each shard.
+pub const SHARD_INDEX_NAME: &str = "__index__.json";
+
+const BLOCK: u64 = 512;
+
+/// One `.krec` member of a shard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardMember {
+    /// Path of the member inside the tar
+    pub name: String,
+    pub uuid: String,
+    pub task: String,
+    /// First frame of the source recording stored in this member
+    pub frame_start: u64,
+    /// End (exclusive) of the source recording's frames stored in this member
+    pub frame_end: u64,
+    /// Number of frames in the whole source recording
+    pub recording_frames: u64,
+    /// Byte offset of the member's data in the shard file
+    pub offset: u64,
+    /// Size of the member's data in bytes
+    pub size: u64,
+}
+
+/// Index of the members of one shard.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardIndex {
+    pub members: Vec<ShardMember>,
+}
+
+/// Space taken in a tar by a member with `len` bytes of data, including its header block.
+fn member_bytes(len: u64) -> u64 {
+    BLOCK + len.div_ceil(BLOCK) * BLOCK
+}
+
+struct OpenShard {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+    index: ShardIndex,
+}
+
+impl OpenShard {
+    fn append(&mut self, name: &str, data: &[u8]) -> Result<u64> {
+        let mut header = tar::Header::new_ustar();
+        header.set_path(name)?;
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_cksum();
+        self.writer.write_all(header.as_bytes())?;
+        let offset = self.size + BLOCK;
+        self.writer.write_all(data)?;
+        let padding = (BLOCK - data.len() as u64 % BLOCK) % BLOCK;
+        self.writer.write_all(&vec![0u8; padding as usize])?;
+        self.size = offset + data.len() as u64 + padding;
+        Ok(offset)
+    }
+}
+
+/// Keeps the characters of `uuid` that are safe in a file name.
+fn member_stem(uuid: &str) -> String {
+    let stem: String = uuid
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(64)
+        .collect();
+    if stem.is_empty() {
+        "recording".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Packs recordings into tar shards of roughly equal size.
+///
+/// Each shard is a plain tar file named `shard-NNNNN.tar` whose members are valid `.krec`
+/// files, followed by a [`SHARD_INDEX_NAME`] member listing them. A recording that does not fit
+/// in the space left in the current shard starts a new one. Only recordings larger than a whole
+/// shard are split by frame range across members; each piece carries the full header, with
+/// `start_timestamp` and `end_timestamp` narrowed to its own frames where it does not contain
+/// the recording's first or last frame. The size limit covers the members and their tar
+/// headers; the index and tar trailer come on top of it, and a single frame larger than the
+/// limit gets a shard of its own.
+pub struct ShardWriter {
+    output_dir: PathBuf,
+    max_shard_bytes: u64,
+    current: Option<OpenShard>,
+    member_count: usize,
+    written: Vec<PathBuf>,
+}
+
+impl ShardWriter {
+    /// Creates `output_dir` if needed. Shards are started as recordings are written.
+    #[instrument]
+    pub fn create(output_dir: &str, max_shard_bytes: u64) -> Result<Self> {
+        fs::create_dir_all(output_dir)?;
+        Ok(Self {
+            output_dir: PathBuf::from(output_dir),
+            max_shard_bytes,
+            current: None,
+            member_count: 0,
+            written: Vec::new(),
+        })
+    }
+
+    fn open_shard(&mut self) -> Result<&mut OpenShard> {
+        if self.current.is_none() {
+            let path = self
+                .output_dir
+                .join(format!("shard-{:05}.tar", self.written.len()));
+            info!("Starting shard: {}", path.display());
+            self.current = Some(OpenShard {
+                writer: BufWriter::new(File::create(&path)?),
+                path,
+                size: 0,
+                index: ShardIndex::default(),
+            });
+        }
+        Ok(self.current.as_mut().expect("shard was just opened"))
+    }
+
+    fn close_shard(&mut self) -> Result<()> {
+        let Some(mut shard) = self.current.take() else {
+            return Ok(());
+        };
+        let index = serde_json::to_vec(&shard.index)?;
+        shard.append(SHARD_INDEX_NAME, &index)?;
+        // End-of-archive marker
+        shard.writer.write_all(&[0u8; 2 * BLOCK as usize])?;
+        shard.writer.flush()?;
+        debug!(
+            "Closed shard {} with {} members ({} bytes)",
+            shard.path.display(),
+            shard.index.members.len(),
+            shard.size
+        );
+        self.written.push(shard.path);
+        Ok(())
+    }
+
+    /// Bytes used by the members of the current shard.
+    fn used_bytes(&self) -> u64 {
+        self.current.as_ref().map_or(0, |shard| shard.size)
+    }
+
+    /// Appends a recording, starting a new shard if it does not fit in the current one and
+    /// splitting it only if it is larger than a whole shard.
+    #[instrument(skip(self, krec))]
+    pub fn write(&mut self, krec: &KRec) -> Result<()> {
+        let frame_sizes: Vec<u64> = krec
+            .frames
+            .iter()
+            .map(|f| 4 + f.encoded_len() as u64)
+            .collect();
+        let header_size = 4 + krec.header.encoded_len() as u64;
+        let total = member_bytes(header_size + frame_sizes.iter().sum::<u64>());
+
+        if self.used_bytes() > 0 && self.used_bytes() + total > self.max_shard_bytes {
+            self.close_shard()?;
+        }
+        if total <= self.max_shard_bytes {
+            return self.write_piece(krec, 0, krec.frames.len());
+        }
+
+        debug!(
+            "Splitting {} byte recording {} across shards",
+            total, krec.header.uuid
+        );
+        let mut start = 0;
+        loop {
+            // Each piece fills what is left of the current shard, with at least one frame
+            let available = self.max_shard_bytes.saturating_sub(self.used_bytes());
+            let mut end = start;
+            let mut size = header_size;
+            while end < frame_sizes.len()
+                && (end == start || member_bytes(size + frame_sizes[end]) <= available)
+            {
+                size += frame_sizes[end];
+                end += 1;
+            }
+            self.write_piece(krec, start, end)?;
+            start = end;
+            if start >= frame_sizes.len() {
+                return Ok(());
+            }
+            self.close_shard()?;
+        }
+    }
+
+    fn write_piece(&mut self, krec: &KRec, start: usize, end: usize) -> Result<()> {
+        let frames = &krec.frames[start..end];
+        let mut header = krec.header.clone();
+        if start > 0 {
+            header.start_timestamp = frames.first().map_or(0, |f| f.real_timestamp);
+        }
+        if end < krec.frames.len() {
+            header.end_timestamp = frames.last().map_or(0, |f| f.real_timestamp);
+        }
+
+        let mut data = Vec::new();
+        for message in std::iter::once(header.encode_to_vec())
+            .chain(frames.iter().map(|frame| frame.encode_to_vec()))
+        {
+            data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+            data.extend_from_slice(&message);
+        }
+
+        let name = format!(
+            "{:06}-{}.krec",
+            self.member_count,
+            member_stem(&krec.header.uuid)
+        );
+        let shard = self.open_shard()?;
+        let offset = shard.append(&name, &data)?;
+        shard.index.members.push(ShardMember {
+            name,
+            uuid: krec.header.uuid.clone(),
+            task: krec.header.task.clone(),
+            frame_start: start as u64,
+            frame_end: end as u64,
+            recording_frames: krec.frames.len() as u64,
+            offset,
+            size: data.len() as u64,
+        });
+        self.member_count += 1;
+        Ok(())
+    }
+
+    /// Finishes the last shard and returns the paths of all shards written.
+    pub fn finish(mut self) -> Result<Vec<PathBuf>> {
+        self.close_shard()?;
+        info!(
+            "Wrote {} members to {} shards",
+            self.member_count,
+            self.written.len()
+        );
+        Ok(self.written)
+    }
+}
+
+/// Reads the [`ShardIndex`] of a shard written by [`ShardWriter`].
+#[instrument]
+pub fn read_shard_index(path: &str) -> Result<ShardIndex> {
+    let mut archive = tar::Archive::new(BufReader::new(File::open(path)?));
+    for entry in archive.entries_with_seek()? {
+        let mut entry = entry?;
+        if entry.path()?.as_os_str() == SHARD_INDEX_NAME {
+            let mut index = Vec::new();
+            entry.read_to_end(&mut index)?;
+            return Ok(serde_json::from_slice(&index)?);
+        }
+    }
+    Err(eyre!("Shard {} has no {} member", path, SHARD_INDEX_NAME))
+}
+
+/// One shard member holding part or all of a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardPiece {
+    pub shard: PathBuf,
+    pub member: ShardMember,
+}
+
+impl ShardPiece {
+    fn open(&self) -> Result<KRecReader<BufReader<std::io::Take<File>>>> {
+        let mut file = File::open(&self.shard)?;
+        file.seek(SeekFrom::Start(self.member.offset))?;
+        KRecReader::new(BufReader::new(file.take(self.member.size)))
+    }
+}
+
+/// A recording in a [`ShardedDataset`], made of one or more consecutive pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardEpisode {
+    pub uuid: String,
+    pub task: String,
+    /// Number of frames in the recording, including any pieces in shards that were not opened
+    pub frame_count: u64,
+    pub pieces: Vec<ShardPiece>,
+}
+
+impl ShardEpisode {
+    /// Whether every frame of the recording is in the opened shards.
+    pub fn is_complete(&self) -> bool {
+        let mut next = 0;
+        for piece in &self.pieces {
+            if piece.member.frame_start != next {
+                return false;
+            }
+            next = piece.member.frame_end;
+        }
+        next == self.frame_count
+    }
+}
+
+/// SplitMix64 step, used for shuffling so that orders are reproducible across platforms.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Random access over the recordings in a set of shards.
+///
+/// Each episode is one recording written by [`ShardWriter`]; recordings that were split across
+/// shards are joined back together when loaded.
+pub struct ShardedDataset {
+    episodes: Vec<ShardEpisode>,
+}
+
+impl ShardedDataset {
+    /// Opens every `.tar` shard directly inside `dir`, in name order.
+    #[instrument]
+    pub fn open(dir: &str) -> Result<Self> {
+        let mut shards = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "tar") {
+                shards.push(path);
+            }
+        }
+        shards.sort();
+        Self::from_shards(&shards)
+    }
+
+    /// Opens the given shards, in order, reading only their indexes.
+    ///
+    /// Pieces continue the previous episode when they have the same UUID and start where it
+    /// ended. Episodes with pieces in shards that were not given are logged and kept partial.
+    pub fn from_shards(shards: &[PathBuf]) -> Result<Self> {
+        let mut episodes: Vec<ShardEpisode> = Vec::new();
+        for shard in shards {
+            let index = read_shard_index(&shard.to_string_lossy())?;
+            for member in index.members {
+                let continues = episodes.last().is_some_and(|episode| {
+                    let last = &episode.pieces[episode.pieces.len() - 1].member;
+                    last.uuid == member.uuid
+                        && member.frame_start > 0
+                        && last.frame_end == member.frame_start
+                });
+                let piece = ShardPiece {
+                    shard: shard.clone(),
+                    member,
+                };
+                match episodes.last_mut() {
+                    Some(episode) if continues => episode.pieces.push(piece),
+                    _ => episodes.push(ShardEpisode {
+                        uuid: piece.member.uuid.clone(),
+                        task: piece.member.task.clone(),
+                        frame_count: piece.member.recording_frames,
+                        pieces: vec![piece],
+                    }),
+                }
+            }
+        }
+        for episode in episodes.iter().filter(|e| !e.is_complete()) {
+            warn!(
+                "Recording {} is incomplete in the opened shards",
+                episode.uuid
+            );
+        }
+        info!(
+            "Opened {} episodes from {} shards",
+            episodes.len(),
+            shards.len()
+        );
+        Ok(Self { episodes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.episodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.episodes.is_empty()
+    }
+
+    pub fn episodes(&self) -> &[ShardEpisode] {
+        &self.episodes
+    }
+
+    /// Indices of the episodes with `uuid`.
+    pub fn find_uuid(&self, uuid: &str) -> Vec<usize> {
+        (0..self.episodes.len())
+            .filter(|&i| self.episodes[i].uuid == uuid)
+            .collect()
+    }
+
+    /// Reads only the header of episode `index`.
+    pub fn read_header(&self, index: usize) -> Result<KRecHeader> {
+        let episode = self.episode(index)?;
+        let mut header = episode.pieces[0].open()?.header().clone();
+        if let Some(last) = episode.pieces.last().filter(|_| episode.pieces.len() > 1) {
+            header.end_timestamp = last.open()?.header().end_timestamp;
+        }
+        Ok(header)
+    }
+
+    /// Reads episode `index`, seeking to each of its pieces.
+    #[instrument(skip(self))]
+    pub fn load(&self, index: usize) -> Result<KRec> {
+        let episode = self.episode(index)?;
+        let mut krec: Option<KRec> = None;
+        for piece in &episode.pieces {
+            debug!(
+                "Reading {} from {}",
+                piece.member.name,
+                piece.shard.display()
+            );
+            let mut reader = piece.open()?;
+            let header = reader.header().clone();
+            let krec = krec.get_or_insert_with(|| KRec::new(header.clone()));
+            // Pieces narrow the header timestamps to their own frames, so the last one holds
+            // the recording's end_timestamp
+            krec.header.end_timestamp = header.end_timestamp;
+            for frame in reader.by_ref() {
+                krec.frames.push(frame?);
+            }
+        }
+        krec.ok_or_else(|| eyre!("Episode {} has no pieces", index))
+    }
+
+    /// Reads the first recording with `uuid`.
+    pub fn load_recording(&self, uuid: &str) -> Result<KRec> {
+        let index = self
+            .find_uuid(uuid)
+            .first()
+            .copied()
+            .ok_or_else(|| eyre!("No recording with UUID '{}'", uuid))?;
+        self.load(index)
+    }
+
+    /// Episode indices in an order that depends only on `seed` and the number of episodes.
+    pub fn shuffled(&self, seed: u64) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.episodes.len()).collect();
+        let mut state = seed;
+        for i in (1..order.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            order.swap(i, j);
+        }
+        order
+    }
+
+    fn episode(&self, index: usize) -> Result<&ShardEpisode> {
+        self.episodes.get(index).ok_or_else(|| {
+            eyre!(
+                "Episode {} out of range for {} episodes",
+                index,
+                self.episodes.len()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorState, KRecFrame};
+    use std::collections::HashMap;
+
+    fn recording(uuid: &str, frames: u64) -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            uuid: uuid.to_string(),
+            task: "walk".to_string(),
+            start_timestamp: 1_000,
+            end_timestamp: 1_000 + frames * 10_000_000,
+            ..Default::default()
+        });
+        for i in 0..frames {
+            krec.frames.push(KRecFrame {
+                real_timestamp: 1_000 + (i + 1) * 10_000_000,
+                video_frame_number: i,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: true,
+                    position: Some(i as f64 * 0.25),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    fn assert_same(actual: &KRec, expected: &KRec) {
+        assert_eq!(actual.header, expected.header);
+        assert_eq!(actual.frames, expected.frames);
+    }
+
+    #[test]
+    fn shard_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_string_lossy().to_string();
+        let recordings = [
+            recording("a", 20),
+            recording("b", 20),
+            recording("c", 20),
+            recording("big", 400),
+            recording("d", 5),
+        ];
+
+        let max_shard_bytes = 4096;
+        let mut writer = ShardWriter::create(&dir_str, max_shard_bytes).unwrap();
+        for krec in &recordings {
+            writer.write(krec).unwrap();
+        }
+        let shards = writer.finish().unwrap();
+        assert!(shards.len() > 2);
+
+        for shard in &shards {
+            let path = shard.to_string_lossy().to_string();
+            let index = read_shard_index(&path).unwrap();
+            let mut archive = tar::Archive::new(File::open(shard).unwrap());
+            let mut entries = HashMap::new();
+            let mut used = 0;
+            for entry in archive.entries_with_seek().unwrap() {
+                let entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().to_string();
+                if name != SHARD_INDEX_NAME {
+                    used += member_bytes(entry.size());
+                }
+                entries.insert(name, (entry.raw_file_position(), entry.size()));
+            }
+            assert!(used <= max_shard_bytes);
+            assert_eq!(entries.len(), index.members.len() + 1);
+            for member in &index.members {
+                assert_eq!(entries[&member.name], (member.offset, member.size));
+            }
+        }
+
+        let dataset = ShardedDataset::open(&dir_str).unwrap();
+        assert_eq!(dataset.len(), recordings.len());
+        for (i, expected) in recordings.iter().enumerate() {
+            let episode = &dataset.episodes()[i];
+            assert_eq!(episode.uuid, expected.header.uuid);
+            assert!(episode.is_complete());
+            assert_eq!(episode.pieces.len() > 1, expected.header.uuid == "big");
+            assert_same(&dataset.load(i).unwrap(), expected);
+            assert_eq!(dataset.read_header(i).unwrap(), expected.header);
+        }
+        assert_same(&dataset.load_recording("big").unwrap(), &recordings[3]);
+        assert!(dataset.load_recording("missing").is_err());
+    }
+
+    #[test]
+    fn split_pieces_are_valid_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_string_lossy().to_string();
+        let krec = recording("big", 400);
+        let mut writer = ShardWriter::create(&dir_str, 4096).unwrap();
+        writer.write(&krec).unwrap();
+        writer.finish().unwrap();
+
+        let dataset = ShardedDataset::open(&dir_str).unwrap();
+        let pieces = &dataset.episodes()[0].pieces;
+        assert!(pieces.len() > 2);
+        for (i, piece) in pieces.iter().enumerate() {
+            let mut reader = piece.open().unwrap();
+            let header = reader.header().clone();
+            let frames: Vec<KRecFrame> = reader.by_ref().map(|f| f.unwrap()).collect();
+            let (start, end) = (piece.member.frame_start, piece.member.frame_end);
+            assert_eq!(frames.len() as u64, end - start);
+            assert_eq!(frames, krec.frames[start as usize..end as usize]);
+            let expected_start = if i == 0 {
+                krec.header.start_timestamp
+            } else {
+                frames[0].real_timestamp
+            };
+            let expected_end = if i == pieces.len() - 1 {
+                krec.header.end_timestamp
+            } else {
+                frames[frames.len() - 1].real_timestamp
+            };
+            assert_eq!(header.start_timestamp, expected_start);
+            assert_eq!(header.end_timestamp, expected_end);
+        }
+
+        // Opening only the later shards keeps the remaining pieces as a partial episode
+        let dataset = ShardedDataset::from_shards(&[pieces[1].shard.clone()]).unwrap();
+        assert_eq!(dataset.len(), 1);
+        assert!(!dataset.episodes()[0].is_complete());
+    }
+
+    #[test]
+    fn shuffled_is_a_deterministic_permutation() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_string_lossy().to_string();
+        let mut writer = ShardWriter::create(&dir_str, 4096).unwrap();
+        for i in 0..10 {
+            writer.write(&recording(&format!("r{}", i), 3)).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let dataset = ShardedDataset::open(&dir_str).unwrap();
+        let order = dataset.shuffled(42);
+        assert_eq!(order, dataset.shuffled(42));
+        assert_ne!(order, dataset.shuffled(43));
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,534 @@
+use crate::proto::{proto::Vec3, KRecFrame};
+use crate::KRec;
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::{debug, instrument};
+
+/// Intervals longer than this multiple of the median interval are reported as gaps.
+const GAP_FACTOR: f64 = 1.5;
+
+/// Summary of a scalar signal (min, max, mean and standard deviation).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std: f64,
+}
+
+/// Statistics over the intervals between consecutive frames, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IntervalStats {
+    pub mean_ns: f64,
+    pub median_ns: f64,
+    pub p99_ns: f64,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    /// Standard deviation of the frame interval
+    pub jitter_ns: f64,
+}
+
+/// A gap between two consecutive frames that is much longer than the median interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FrameGap {
+    /// Index of the frame preceding the gap
+    pub frame_index: usize,
+    pub interval_ns: u64,
+    /// Estimated number of frames missing from the gap
+    pub dropped_frames: u64,
+}
+
+/// Per-axis statistics for a 3D vector signal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Vec3Stats {
+    pub x: FieldStats,
+    pub y: FieldStats,
+    pub z: FieldStats,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ActuatorStats {
+    pub actuator_id: u32,
+    pub name: Option<String>,
+    /// Number of frames containing a state for this actuator
+    pub samples: usize,
+    /// Fraction of states reporting the actuator as offline
+    pub offline_fraction: f64,
+    pub position: Option<FieldStats>,
+    pub velocity: Option<FieldStats>,
+    pub torque: Option<FieldStats>,
+    pub temperature: Option<FieldStats>,
+    pub voltage: Option<FieldStats>,
+    pub current: Option<FieldStats>,
+    /// Fraction of frames containing a command for this actuator
+    pub command_coverage: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ImuStats {
+    /// Fraction of frames containing IMU values
+    pub coverage: f64,
+    pub accel: Option<Vec3Stats>,
+    pub gyro: Option<Vec3Stats>,
+    pub mag: Option<Vec3Stats>,
+}
+
+/// Summary statistics for a whole recording, as returned by [`KRec::stats`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct KRecStats {
+    pub frame_count: usize,
+    /// Time between the first and last frame, from `real_timestamp`
+    pub duration_ns: u64,
+    pub interval: Option<IntervalStats>,
+    pub gaps: Vec<FrameGap>,
+    /// Fraction of frames containing at least one actuator command
+    pub command_coverage: f64,
+    pub actuators: Vec<ActuatorStats>,
+    pub imu: ImuStats,
+}
+
+impl KRecStats {
+    /// Mean frame rate in Hz, derived from the mean frame interval.
+    pub fn mean_rate_hz(&self) -> Option<f64> {
+        self.interval
+            .filter(|i| i.mean_ns > 0.0)
+            .map(|i| 1e9 / i.mean_ns)
+    }
+
+    /// Total number of frames estimated to be missing across all gaps.
+    pub fn dropped_frames(&self) -> u64 {
+        self.gaps.iter().map(|g| g.dropped_frames).sum()
+    }
+}
+
+/// Running accumulator using Welford's algorithm.
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Accumulator {
+    fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn push_opt(&mut self, value: Option<f64>) {
+        if let Some(value) = value {
+            self.push(value);
+        }
+    }
+
+    fn finish(&self) -> Option<FieldStats> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(FieldStats {
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            mean: self.mean,
+            std: (self.m2 / self.count as f64).sqrt(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Vec3Accumulator {
+    x: Accumulator,
+    y: Accumulator,
+    z: Accumulator,
+}
+
+impl Vec3Accumulator {
+    fn push(&mut self, v: &Vec3) {
+        self.x.push(v.x);
+        self.y.push(v.y);
+        self.z.push(v.z);
+    }
+
+    fn finish(&self) -> Option<Vec3Stats> {
+        Some(Vec3Stats {
+            x: self.x.finish()?,
+            y: self.y.finish()?,
+            z: self.z.finish()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ActuatorAccumulator {
+    samples: usize,
+    offline: usize,
+    commands: usize,
+    position: Accumulator,
+    velocity: Accumulator,
+    torque: Accumulator,
+    temperature: Accumulator,
+    voltage: Accumulator,
+    current: Accumulator,
+}
+
+/// Nearest-rank percentile of an already sorted slice.
+fn percentile(sorted: &[u64], p: f64) -> f64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1] as f64
+}
+
+fn interval_stats(frames: &[KRecFrame]) -> (Option<IntervalStats>, Vec<FrameGap>) {
+    let intervals: Vec<u64> = frames
+        .windows(2)
+        .map(|w| w[1].real_timestamp.saturating_sub(w[0].real_timestamp))
+        .collect();
+    if intervals.is_empty() {
+        return (None, Vec::new());
+    }
+
+    let mut acc = Accumulator::default();
+    for &interval in &intervals {
+        acc.push(interval as f64);
+    }
+    let mut sorted = intervals.clone();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    let median_ns = if sorted.len() % 2 == 1 {
+        sorted[mid] as f64
+    } else {
+        (sorted[mid - 1] + sorted[mid]) as f64 / 2.0
+    };
+
+    let stats = IntervalStats {
+        mean_ns: acc.mean,
+        median_ns,
+        p99_ns: percentile(&sorted, 99.0),
+        min_ns: sorted[0],
+        max_ns: sorted[sorted.len() - 1],
+        jitter_ns: (acc.m2 / acc.count as f64).sqrt(),
+    };
+
+    let mut gaps = Vec::new();
+    if median_ns > 0.0 {
+        for (i, &interval) in intervals.iter().enumerate() {
+            if interval as f64 > GAP_FACTOR * median_ns {
+                gaps.push(FrameGap {
+                    frame_index: i,
+                    interval_ns: interval,
+                    dropped_frames: ((interval as f64 / median_ns).round() as u64)
+                        .saturating_sub(1),
+                });
+            }
+        }
+    }
+
+    (Some(stats), gaps)
+}
+
+impl KRec {
+    /// Median interval between consecutive frames in nanoseconds, from `real_timestamp`.
+    pub(crate) fn median_frame_interval_ns(&self) -> Option<f64> {
+        interval_stats(&self.frames).0.map(|i| i.median_ns)
+    }
+
+    /// Computes summary statistics over all frames of the recording.
+    #[instrument(skip(self))]
+    pub fn stats(&self) -> KRecStats {
+        let frame_count = self.frames.len();
+        debug!("Computing stats over {} frames", frame_count);
+
+        let duration_ns = match (self.frames.first(), self.frames.last()) {
+            (Some(first), Some(last)) => last.real_timestamp.saturating_sub(first.real_timestamp),
+            _ => 0,
+        };
+        let (interval, gaps) = interval_stats(&self.frames);
+
+        let mut actuators: BTreeMap<u32, ActuatorAccumulator> = self
+            .header
+            .actuator_configs
+            .iter()
+            .map(|c| (c.actuator_id, ActuatorAccumulator::default()))
+            .collect();
+        let mut frames_with_commands = 0;
+        let mut frames_with_imu = 0;
+        let mut accel = Vec3Accumulator::default();
+        let mut gyro = Vec3Accumulator::default();
+        let mut mag = Vec3Accumulator::default();
+
+        for frame in &self.frames {
+            for state in &frame.actuator_states {
+                let acc = actuators.entry(state.actuator_id).or_default();
+                acc.samples += 1;
+                if !state.online {
+                    acc.offline += 1;
+                }
+                acc.position.push_opt(state.position);
+                acc.velocity.push_opt(state.velocity);
+                acc.torque.push_opt(state.torque);
+                acc.temperature.push_opt(state.temperature);
+                acc.voltage.push_opt(state.voltage.map(f64::from));
+                acc.current.push_opt(state.current.map(f64::from));
+            }
+
+            if !frame.actuator_commands.is_empty() {
+                frames_with_commands += 1;
+            }
+            for command in &frame.actuator_commands {
+                actuators.entry(command.actuator_id).or_default().commands += 1;
+            }
+
+            if let Some(imu) = &frame.imu_values {
+                frames_with_imu += 1;
+                if let Some(v) = &imu.accel {
+                    accel.push(v);
+                }
+                if let Some(v) = &imu.gyro {
+                    gyro.push(v);
+                }
+                if let Some(v) = &imu.mag {
+                    mag.push(v);
+                }
+            }
+        }
+
+        let fraction = |n: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                n as f64 / total as f64
+            }
+        };
+
+        let actuators = actuators
+            .into_iter()
+            .map(|(actuator_id, acc)| ActuatorStats {
+                actuator_id,
+                name: self
+                    .header
+                    .actuator_configs
+                    .iter()
+                    .find(|c| c.actuator_id == actuator_id)
+                    .and_then(|c| c.name.clone()),
+                samples: acc.samples,
+                offline_fraction: fraction(acc.offline, acc.samples),
+                position: acc.position.finish(),
+                velocity: acc.velocity.finish(),
+                torque: acc.torque.finish(),
+                temperature: acc.temperature.finish(),
+                voltage: acc.voltage.finish(),
+                current: acc.current.finish(),
+                command_coverage: fraction(acc.commands, frame_count),
+            })
+            .collect();
+
+        KRecStats {
+            frame_count,
+            duration_ns,
+            interval,
+            gaps,
+            command_coverage: fraction(frames_with_commands, frame_count),
+            actuators,
+            imu: ImuStats {
+                coverage: fraction(frames_with_imu, frame_count),
+                accel: accel.finish(),
+                gyro: gyro.finish(),
+                mag: mag.finish(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for FieldStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min={:.4} max={:.4} mean={:.4} std={:.4}",
+            self.min, self.max, self.mean, self.std
+        )
+    }
+}
+
+impl fmt::Display for KRecStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Frames: {}", self.frame_count)?;
+        writeln!(f, "Duration: {:.3} s", self.duration_ns as f64 / 1e9)?;
+        if let Some(interval) = &self.interval {
+            writeln!(
+                f,
+                "Frame interval: mean={:.3} ms, median={:.3} ms, p99={:.3} ms, jitter={:.3} ms",
+                interval.mean_ns / 1e6,
+                interval.median_ns / 1e6,
+                interval.p99_ns / 1e6,
+                interval.jitter_ns / 1e6
+            )?;
+        }
+        if let Some(rate) = self.mean_rate_hz() {
+            writeln!(f, "Mean rate: {:.2} Hz", rate)?;
+        }
+        writeln!(
+            f,
+            "Gaps: {} ({} dropped frames)",
+            self.gaps.len(),
+            self.dropped_frames()
+        )?;
+        writeln!(f, "Command coverage: {:.1}%", self.command_coverage * 100.0)?;
+
+        for actuator in &self.actuators {
+            write!(f, "\nActuator {}", actuator.actuator_id)?;
+            if let Some(name) = &actuator.name {
+                write!(f, " ({})", name)?;
+            }
+            writeln!(
+                f,
+                ": samples={}, offline={:.1}%, commands={:.1}%",
+                actuator.samples,
+                actuator.offline_fraction * 100.0,
+                actuator.command_coverage * 100.0
+            )?;
+            let fields = [
+                ("position", &actuator.position),
+                ("velocity", &actuator.velocity),
+                ("torque", &actuator.torque),
+                ("temperature", &actuator.temperature),
+                ("voltage", &actuator.voltage),
+                ("current", &actuator.current),
+            ];
+            for (name, stats) in fields {
+                if let Some(stats) = stats {
+                    writeln!(f, "  {}: {}", name, stats)?;
+                }
+            }
+        }
+
+        writeln!(f, "\nIMU coverage: {:.1}%", self.imu.coverage * 100.0)?;
+        let vectors = [
+            ("accel", &self.imu.accel),
+            ("gyro", &self.imu.gyro),
+            ("mag", &self.imu.mag),
+        ];
+        for (name, stats) in vectors {
+            if let Some(stats) = stats {
+                writeln!(f, "  {}.x: {}", name, stats.x)?;
+                writeln!(f, "  {}.y: {}", name, stats.y)?;
+                writeln!(f, "  {}.z: {}", name, stats.z)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorCommand, ActuatorState, KRecHeader};
+
+    fn recording(timestamps: &[u64]) -> KRec {
+        let mut krec = KRec::new(KRecHeader::default());
+        for (i, &timestamp) in timestamps.iter().enumerate() {
+            krec.add_frame(KRecFrame {
+                real_timestamp: timestamp,
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: i != 0,
+                    position: Some(i as f64),
+                    ..Default::default()
+                }],
+                actuator_commands: if i % 2 == 0 {
+                    vec![ActuatorCommand {
+                        actuator_id: 1,
+                        ..Default::default()
+                    }]
+                } else {
+                    vec![]
+                },
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    #[test]
+    fn frame_count_duration_and_rate() {
+        // 10 ms frames with one dropped frame between the third and fourth
+        let stats = recording(&[0, 10_000_000, 20_000_000, 40_000_000, 50_000_000]).stats();
+        assert_eq!(stats.frame_count, 5);
+        assert_eq!(stats.duration_ns, 50_000_000);
+        let interval = stats.interval.unwrap();
+        assert_eq!(interval.mean_ns, 12_500_000.0);
+        assert_eq!(interval.median_ns, 10_000_000.0);
+        assert_eq!(stats.mean_rate_hz(), Some(80.0));
+        assert_eq!(
+            stats.gaps,
+            vec![FrameGap {
+                frame_index: 2,
+                interval_ns: 20_000_000,
+                dropped_frames: 1,
+            }]
+        );
+        assert_eq!(stats.dropped_frames(), 1);
+        assert_eq!(stats.command_coverage, 0.6);
+
+        let actuator = &stats.actuators[0];
+        assert_eq!(actuator.samples, 5);
+        assert_eq!(actuator.offline_fraction, 0.2);
+        let position = actuator.position.unwrap();
+        assert_eq!((position.min, position.max, position.mean), (0.0, 4.0, 2.0));
+    }
+
+    #[test]
+    fn empty_recording() {
+        let stats = recording(&[]).stats();
+        assert_eq!(stats.frame_count, 0);
+        assert_eq!(stats.duration_ns, 0);
+        assert_eq!(stats.interval, None);
+        assert_eq!(stats.mean_rate_hz(), None);
+        assert!(stats.gaps.is_empty());
+        assert_eq!(stats.command_coverage, 0.0);
+        assert_eq!(stats.imu.coverage, 0.0);
+        assert!(stats.actuators.is_empty());
+        stats.to_string();
+    }
+
+    #[test]
+    fn single_frame() {
+        let stats = recording(&[5_000_000]).stats();
+        assert_eq!(stats.frame_count, 1);
+        assert_eq!(stats.duration_ns, 0);
+        assert_eq!(stats.interval, None);
+        assert_eq!(stats.mean_rate_hz(), None);
+        assert_eq!(stats.command_coverage, 1.0);
+        assert_eq!(stats.actuators[0].offline_fraction, 1.0);
+        stats.to_string();
+    }
+
+    #[test]
+    fn identical_timestamps_have_no_rate() {
+        let stats = recording(&[7, 7, 7]).stats();
+        assert_eq!(stats.interval.unwrap().mean_ns, 0.0);
+        assert_eq!(stats.mean_rate_hz(), None);
+        assert!(stats.gaps.is_empty());
+    }
+}
@@ -0,0 +1,697 @@
+use crate::proto::{
+    proto::Vec3, ActuatorCommand, ActuatorState, ImuQuaternion, ImuValues, KRecFrame, KRecHeader,
+};
+use color_eyre::{eyre::eyre, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// Type of the values stored in a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    UInt64,
+    Bool,
+    Float32,
+    Float64,
+}
+
+/// A single cell of a wide table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    UInt64(u64),
+    Bool(bool),
+    Float32(f32),
+    Float64(f64),
+}
+
+impl Value {
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Value::UInt64(v) => v as f64,
+            Value::Bool(v) => f64::from(u8::from(v)),
+            Value::Float32(v) => f64::from(v),
+            Value::Float64(v) => v,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Result<u64> {
+        match *self {
+            Value::UInt64(v) => Ok(v),
+            Value::Float32(v) if v >= 0.0 && v.fract() == 0.0 => Ok(v as u64),
+            Value::Float64(v) if v >= 0.0 && v.fract() == 0.0 => Ok(v as u64),
+            other => Err(eyre!("Expected an unsigned integer, got {}", other)),
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> bool {
+        match *self {
+            Value::Bool(v) => v,
+            other => other.as_f64() != 0.0,
+        }
+    }
+
+    /// Parses a textual cell (e.g. from CSV) as a value of type `ty`.
+    pub fn parse(text: &str, ty: ColumnType) -> Result<Self> {
+        let text = text.trim();
+        let value = match ty {
+            ColumnType::UInt64 => Value::UInt64(text.parse()?),
+            ColumnType::Bool => Value::Bool(match text.to_ascii_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(eyre!("Invalid boolean value '{}'", text)),
+            }),
+            ColumnType::Float32 => Value::Float32(text.parse()?),
+            ColumnType::Float64 => Value::Float64(text.parse()?),
+        };
+        Ok(value)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::UInt64(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Float32(v) => write!(f, "{}", v),
+            Value::Float64(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Per-frame columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameField {
+    RealTimestamp,
+    VideoTimestamp,
+    VideoFrameNumber,
+    InferenceStep,
+}
+
+impl FrameField {
+    pub const ALL: [FrameField; 4] = [
+        FrameField::RealTimestamp,
+        FrameField::VideoTimestamp,
+        FrameField::VideoFrameNumber,
+        FrameField::InferenceStep,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameField::RealTimestamp => "real_timestamp",
+            FrameField::VideoTimestamp => "video_timestamp",
+            FrameField::VideoFrameNumber => "video_frame_number",
+            FrameField::InferenceStep => "inference_step",
+        }
+    }
+
+    pub fn get(&self, frame: &KRecFrame) -> u64 {
+        match self {
+            FrameField::RealTimestamp => frame.real_timestamp,
+            FrameField::VideoTimestamp => frame.video_timestamp,
+            FrameField::VideoFrameNumber => frame.video_frame_number,
+            FrameField::InferenceStep => frame.inference_step,
+        }
+    }
+
+    pub(crate) fn set(&self, frame: &mut KRecFrame, value: u64) {
+        match self {
+            FrameField::RealTimestamp => frame.real_timestamp = value,
+            FrameField::VideoTimestamp => frame.video_timestamp = value,
+            FrameField::VideoFrameNumber => frame.video_frame_number = value,
+            FrameField::InferenceStep => frame.inference_step = value,
+        }
+    }
+}
+
+/// Per-actuator columns, covering both `ActuatorState` and `ActuatorCommand`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActuatorField {
+    Online,
+    Position,
+    Velocity,
+    Torque,
+    Temperature,
+    Voltage,
+    Current,
+    CommandPosition,
+    CommandVelocity,
+    CommandTorque,
+}
+
+impl ActuatorField {
+    pub const ALL: [ActuatorField; 10] = [
+        ActuatorField::Online,
+        ActuatorField::Position,
+        ActuatorField::Velocity,
+        ActuatorField::Torque,
+        ActuatorField::Temperature,
+        ActuatorField::Voltage,
+        ActuatorField::Current,
+        ActuatorField::CommandPosition,
+        ActuatorField::CommandVelocity,
+        ActuatorField::CommandTorque,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActuatorField::Online => "online",
+            ActuatorField::Position => "position",
+            ActuatorField::Velocity => "velocity",
+            ActuatorField::Torque => "torque",
+            ActuatorField::Temperature => "temperature",
+            ActuatorField::Voltage => "voltage",
+            ActuatorField::Current => "current",
+            ActuatorField::CommandPosition => "cmd_position",
+            ActuatorField::CommandVelocity => "cmd_velocity",
+            ActuatorField::CommandTorque => "cmd_torque",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            ActuatorField::Online => ColumnType::Bool,
+            ActuatorField::Voltage
+            | ActuatorField::Current
+            | ActuatorField::CommandPosition
+            | ActuatorField::CommandVelocity
+            | ActuatorField::CommandTorque => ColumnType::Float32,
+            _ => ColumnType::Float64,
+        }
+    }
+
+    pub fn is_command(&self) -> bool {
+        matches!(
+            self,
+            ActuatorField::CommandPosition
+                | ActuatorField::CommandVelocity
+                | ActuatorField::CommandTorque
+        )
+    }
+
+    pub fn get_state(&self, state: &ActuatorState) -> Option<Value> {
+        match self {
+            ActuatorField::Online => Some(Value::Bool(state.online)),
+            ActuatorField::Position => state.position.map(Value::Float64),
+            ActuatorField::Velocity => state.velocity.map(Value::Float64),
+            ActuatorField::Torque => state.torque.map(Value::Float64),
+            ActuatorField::Temperature => state.temperature.map(Value::Float64),
+            ActuatorField::Voltage => state.voltage.map(Value::Float32),
+            ActuatorField::Current => state.current.map(Value::Float32),
+            _ => None,
+        }
+    }
+
+    pub fn get_command(&self, command: &ActuatorCommand) -> Option<Value> {
+        match self {
+            ActuatorField::CommandPosition => Some(Value::Float32(command.position)),
+            ActuatorField::CommandVelocity => Some(Value::Float32(command.velocity)),
+            ActuatorField::CommandTorque => Some(Value::Float32(command.torque)),
+            _ => None,
+        }
+    }
+
+    /// Value of this field for `actuator_id` in `frame`, if present.
+    pub fn get(&self, frame: &KRecFrame, actuator_id: u32) -> Option<Value> {
+        if self.is_command() {
+            frame
+                .actuator_commands
+                .iter()
+                .find(|c| c.actuator_id == actuator_id)
+                .and_then(|c| self.get_command(c))
+        } else {
+            frame
+                .actuator_states
+                .iter()
+                .find(|s| s.actuator_id == actuator_id)
+                .and_then(|s| self.get_state(s))
+        }
+    }
+
+    pub(crate) fn set_state(&self, state: &mut ActuatorState, value: Value) {
+        match self {
+            ActuatorField::Online => state.online = value.as_bool(),
+            ActuatorField::Position => state.position = Some(value.as_f64()),
+            ActuatorField::Velocity => state.velocity = Some(value.as_f64()),
+            ActuatorField::Torque => state.torque = Some(value.as_f64()),
+            ActuatorField::Temperature => state.temperature = Some(value.as_f64()),
+            ActuatorField::Voltage => state.voltage = Some(value.as_f64() as f32),
+            ActuatorField::Current => state.current = Some(value.as_f64() as f32),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn set_command(&self, command: &mut ActuatorCommand, value: Value) {
+        match self {
+            ActuatorField::CommandPosition => command.position = value.as_f64() as f32,
+            ActuatorField::CommandVelocity => command.velocity = value.as_f64() as f32,
+            ActuatorField::CommandTorque => command.torque = value.as_f64() as f32,
+            _ => {}
+        }
+    }
+}
+
+/// IMU columns, one per vector or quaternion component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImuField {
+    Accel(Axis),
+    Gyro(Axis),
+    Mag(Axis),
+    Quaternion(QuaternionComponent),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuaternionComponent {
+    X,
+    Y,
+    Z,
+    W,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Axis::X => "x",
+            Axis::Y => "y",
+            Axis::Z => "z",
+        }
+    }
+
+    fn get(&self, v: &Vec3) -> f64 {
+        match self {
+            Axis::X => v.x,
+            Axis::Y => v.y,
+            Axis::Z => v.z,
+        }
+    }
+
+    fn set(&self, v: &mut Vec3, value: f64) {
+        match self {
+            Axis::X => v.x = value,
+            Axis::Y => v.y = value,
+            Axis::Z => v.z = value,
+        }
+    }
+}
+
+impl QuaternionComponent {
+    pub const ALL: [QuaternionComponent; 4] = [
+        QuaternionComponent::X,
+        QuaternionComponent::Y,
+        QuaternionComponent::Z,
+        QuaternionComponent::W,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            QuaternionComponent::X => "x",
+            QuaternionComponent::Y => "y",
+            QuaternionComponent::Z => "z",
+            QuaternionComponent::W => "w",
+        }
+    }
+
+    fn get(&self, q: &ImuQuaternion) -> f64 {
+        match self {
+            QuaternionComponent::X => q.x,
+            QuaternionComponent::Y => q.y,
+            QuaternionComponent::Z => q.z,
+            QuaternionComponent::W => q.w,
+        }
+    }
+
+    fn set(&self, q: &mut ImuQuaternion, value: f64) {
+        match self {
+            QuaternionComponent::X => q.x = value,
+            QuaternionComponent::Y => q.y = value,
+            QuaternionComponent::Z => q.z = value,
+            QuaternionComponent::W => q.w = value,
+        }
+    }
+}
+
+impl ImuField {
+    pub fn all() -> Vec<ImuField> {
+        let mut fields = Vec::new();
+        fields.extend(Axis::ALL.map(ImuField::Accel));
+        fields.extend(Axis::ALL.map(ImuField::Gyro));
+        fields.extend(Axis::ALL.map(ImuField::Mag));
+        fields.extend(QuaternionComponent::ALL.map(ImuField::Quaternion));
+        fields
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ImuField::Accel(axis) => format!("imu_accel_{}", axis.name()),
+            ImuField::Gyro(axis) => format!("imu_gyro_{}", axis.name()),
+            ImuField::Mag(axis) => format!("imu_mag_{}", axis.name()),
+            ImuField::Quaternion(c) => format!("imu_quat_{}", c.name()),
+        }
+    }
+
+    pub fn get(&self, frame: &KRecFrame) -> Option<f64> {
+        let imu = frame.imu_values.as_ref()?;
+        match self {
+            ImuField::Accel(axis) => imu.accel.as_ref().map(|v| axis.get(v)),
+            ImuField::Gyro(axis) => imu.gyro.as_ref().map(|v| axis.get(v)),
+            ImuField::Mag(axis) => imu.mag.as_ref().map(|v| axis.get(v)),
+            ImuField::Quaternion(c) => imu.quaternion.as_ref().map(|q| c.get(q)),
+        }
+    }
+
+    pub(crate) fn set(&self, imu: &mut ImuValues, value: f64) {
+        match self {
+            ImuField::Accel(axis) => axis.set(imu.accel.get_or_insert_with(Vec3::default), value),
+            ImuField::Gyro(axis) => axis.set(imu.gyro.get_or_insert_with(Vec3::default), value),
+            ImuField::Mag(axis) => axis.set(imu.mag.get_or_insert_with(Vec3::default), value),
+            ImuField::Quaternion(c) => c.set(
+                imu.quaternion.get_or_insert_with(ImuQuaternion::default),
+                value,
+            ),
+        }
+    }
+}
+
+/// A column of the wide (one row per frame) table layout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Frame(FrameField),
+    Actuator {
+        actuator_id: u32,
+        field: ActuatorField,
+    },
+    Imu(ImuField),
+}
+
+impl Column {
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            Column::Frame(_) => ColumnType::UInt64,
+            Column::Actuator { field, .. } => field.column_type(),
+            Column::Imu(_) => ColumnType::Float64,
+        }
+    }
+}
+
+/// Wide table layout: one row per frame and one column per frame, actuator or IMU field.
+///
+/// Actuator columns are named `<prefix>_<field>` (e.g. `act_12_position`, `act_12_cmd_torque`),
+/// where the prefix is the actuator's `ActuatorConfig.name` when present and `act_<id>`
+/// otherwise. Names that are empty, repeat an earlier actuator's name or match another
+/// actuator's `act_<id>` fall back to `act_<id>` too, so prefixes are unique. IMU columns are named like `imu_accel_x` and `imu_quat_w`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WideLayout {
+    pub actuator_ids: Vec<u32>,
+    pub columns: Vec<Column>,
+    prefixes: HashMap<u32, String>,
+}
+
+/// Makes an actuator name safe for use in a column name.
+fn sanitize(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn actuator_prefixes(header: &KRecHeader, actuator_ids: &[u32]) -> HashMap<u32, String> {
+    let fallback = |id: u32| format!("act_{}", id);
+    let mut prefixes = HashMap::new();
+    // Fallback names are reserved up front, so a name can't take another actuator's fallback
+    let mut used: BTreeSet<String> = actuator_ids.iter().map(|&id| fallback(id)).collect();
+    for &id in actuator_ids {
+        let named = header
+            .actuator_configs
+            .iter()
+            .find(|c| c.actuator_id == id)
+            .and_then(|c| c.name.as_deref())
+            .map(sanitize)
+            .filter(|name| !name.is_empty() && !used.contains(name));
+        let prefix = named.unwrap_or_else(|| fallback(id));
+        used.insert(prefix.clone());
+        prefixes.insert(id, prefix);
+    }
+    prefixes
+}
+
+impl WideLayout {
+    /// Builds the layout for a recording, covering every actuator in the header's configs (in
+    /// order) followed by any other actuators that appear in the frames (sorted by ID).
+    pub fn new(header: &KRecHeader, frames: &[KRecFrame]) -> Self {
+        let mut actuator_ids: Vec<u32> = header
+            .actuator_configs
+            .iter()
+            .map(|c| c.actuator_id)
+            .collect();
+        let extra: BTreeSet<u32> = frames
+            .iter()
+            .flat_map(|f| {
+                f.actuator_states
+                    .iter()
+                    .map(|s| s.actuator_id)
+                    .chain(f.actuator_commands.iter().map(|c| c.actuator_id))
+            })
+            .filter(|id| !actuator_ids.contains(id))
+            .collect();
+        actuator_ids.extend(extra);
+        Self::with_actuators(header, actuator_ids)
+    }
+
+    /// Builds the layout for an explicit list of actuators.
+    pub fn with_actuators(header: &KRecHeader, actuator_ids: Vec<u32>) -> Self {
+        let prefixes = actuator_prefixes(header, &actuator_ids);
+        let mut columns: Vec<Column> = FrameField::ALL.into_iter().map(Column::Frame).collect();
+        for &actuator_id in &actuator_ids {
+            for field in ActuatorField::ALL {
+                columns.push(Column::Actuator { actuator_id, field });
+            }
+        }
+        columns.extend(ImuField::all().into_iter().map(Column::Imu));
+        Self {
+            actuator_ids,
+            columns,
+            prefixes,
+        }
+    }
+
+    /// Reconstructs a layout from column names, resolving actuator names via `header`.
+    ///
+    /// Columns may appear in any order, but every column must be recognised and
+    /// `real_timestamp` must be present.
+    pub fn from_column_names(header: &KRecHeader, names: &[String]) -> Result<Self> {
+        let mut by_prefix: HashMap<String, u32> = HashMap::new();
+        let config_ids: Vec<u32> = header
+            .actuator_configs
+            .iter()
+            .map(|c| c.actuator_id)
+            .collect();
+        for (id, prefix) in actuator_prefixes(header, &config_ids) {
+            by_prefix.insert(prefix, id);
+        }
+
+        let imu_fields = ImuField::all();
+        let mut actuator_ids = Vec::new();
+        let mut columns = Vec::new();
+        let mut unknown = Vec::new();
+        for name in names {
+            let column = if let Some(field) = FrameField::ALL.iter().find(|f| f.name() == name) {
+                Column::Frame(*field)
+            } else if let Some(field) = imu_fields.iter().find(|f| f.name() == *name) {
+                Column::Imu(*field)
+            } else if let Some(column) = parse_actuator_column(name, &by_prefix) {
+                column
+            } else {
+                unknown.push(name.as_str());
+                continue;
+            };
+
+            if let Column::Actuator { actuator_id, .. } = column {
+                if !actuator_ids.contains(&actuator_id) {
+                    actuator_ids.push(actuator_id);
+                }
+            }
+            columns.push(column);
+        }
+
+        if !unknown.is_empty() {
+            return Err(eyre!("Unrecognised columns: {}", unknown.join(", ")));
+        }
+        if !columns.contains(&Column::Frame(FrameField::RealTimestamp)) {
+            return Err(eyre!("Missing required column 'real_timestamp'"));
+        }
+
+        let prefixes = actuator_prefixes(header, &actuator_ids);
+        Ok(Self {
+            actuator_ids,
+            columns,
+            prefixes,
+        })
+    }
+
+    pub fn column_name(&self, column: &Column) -> String {
+        match column {
+            Column::Frame(field) => field.name().to_string(),
+            Column::Actuator { actuator_id, field } => {
+                format!("{}_{}", self.prefix(*actuator_id), field.name())
+            }
+            Column::Imu(field) => field.name(),
+        }
+    }
+
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns.iter().map(|c| self.column_name(c)).collect()
+    }
+
+    /// Column prefix used for an actuator.
+    pub fn prefix(&self, actuator_id: u32) -> String {
+        self.prefixes
+            .get(&actuator_id)
+            .cloned()
+            .unwrap_or_else(|| format!("act_{}", actuator_id))
+    }
+
+    /// Value of `column` in `frame`, or `None` if it is missing.
+    pub fn value(&self, column: &Column, frame: &KRecFrame) -> Option<Value> {
+        match column {
+            Column::Frame(field) => Some(Value::UInt64(field.get(frame))),
+            Column::Actuator { actuator_id, field } => field.get(frame, *actuator_id),
+            Column::Imu(field) => field.get(frame).map(Value::Float64),
+        }
+    }
+
+    /// Flattens a frame into one value per column.
+    pub fn row(&self, frame: &KRecFrame) -> Vec<Option<Value>> {
+        self.columns.iter().map(|c| self.value(c, frame)).collect()
+    }
+
+    /// Rebuilds a frame from one value per column.
+    ///
+    /// An actuator state (or command) is created when any of its columns has a value; IMU
+    /// vectors are created when any of their components has a value.
+    pub fn frame_from_row(&self, row: &[Option<Value>]) -> Result<KRecFrame> {
+        if row.len() != self.columns.len() {
+            return Err(eyre!(
+                "Row has {} values, expected {}",
+                row.len(),
+                self.columns.len()
+            ));
+        }
+
+        let mut frame = KRecFrame::default();
+        let mut states: Vec<ActuatorState> = Vec::new();
+        let mut commands: Vec<ActuatorCommand> = Vec::new();
+        let mut imu: Option<ImuValues> = None;
+
+        for (column, value) in self.columns.iter().zip(row) {
+            let Some(value) = value else {
+                continue;
+            };
+            match column {
+                Column::Frame(field) => field.set(&mut frame, value.as_u64()?),
+                Column::Actuator { actuator_id, field } if field.is_command() => {
+                    let command = match commands.iter().position(|c| c.actuator_id == *actuator_id)
+                    {
+                        Some(i) => &mut commands[i],
+                        None => {
+                            commands.push(ActuatorCommand {
+                                actuator_id: *actuator_id,
+                                ..Default::default()
+                            });
+                            commands.last_mut().unwrap()
+                        }
+                    };
+                    field.set_command(command, *value);
+                }
+                Column::Actuator { actuator_id, field } => {
+                    let state = match states.iter().position(|s| s.actuator_id == *actuator_id) {
+                        Some(i) => &mut states[i],
+                        None => {
+                            states.push(ActuatorState {
+                                actuator_id: *actuator_id,
+                                ..Default::default()
+                            });
+                            states.last_mut().unwrap()
+                        }
+                    };
+                    field.set_state(state, *value);
+                }
+                Column::Imu(field) => {
+                    field.set(imu.get_or_insert_with(ImuValues::default), value.as_f64())
+                }
+            }
+        }
+
+        // Keep the layout's actuator order rather than column order
+        states.sort_by_key(|s| self.actuator_ids.iter().position(|id| *id == s.actuator_id));
+        commands.sort_by_key(|c| self.actuator_ids.iter().position(|id| *id == c.actuator_id));
+        frame.actuator_states = states;
+        frame.actuator_commands = commands;
+        frame.imu_values = imu;
+        Ok(frame)
+    }
+}
+
+fn parse_actuator_column(name: &str, by_prefix: &HashMap<String, u32>) -> Option<Column> {
+    ActuatorField::ALL.into_iter().find_map(|field| {
+        let prefix = name.strip_suffix(field.name())?.strip_suffix('_')?;
+        let actuator_id = match by_prefix.get(prefix) {
+            Some(id) => *id,
+            None => prefix.strip_prefix("act_")?.parse().ok()?,
+        };
+        Some(Column::Actuator { actuator_id, field })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::ActuatorConfig;
+
+    fn header(names: &[(u32, &str)]) -> KRecHeader {
+        KRecHeader {
+            actuator_configs: names
+                .iter()
+                .map(|&(actuator_id, name)| ActuatorConfig {
+                    actuator_id,
+                    name: Some(name.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prefixes_are_unique() {
+        let header = header(&[
+            (1, "left knee"),
+            (2, "act_5"),
+            (3, "left_knee"),
+            (4, ""),
+            (6, "act_6"),
+        ]);
+        let prefixes = actuator_prefixes(&header, &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(prefixes[&1], "left_knee");
+        // Actuator 5 is unnamed, so "act_5" stays its fallback
+        assert_eq!(prefixes[&2], "act_2");
+        assert_eq!(prefixes[&3], "act_3");
+        assert_eq!(prefixes[&4], "act_4");
+        assert_eq!(prefixes[&5], "act_5");
+        assert_eq!(prefixes[&6], "act_6");
+
+        let layout = WideLayout::with_actuators(&header, vec![1, 2, 3, 4, 5, 6]);
+        let names: BTreeSet<String> = layout.column_names().into_iter().collect();
+        assert_eq!(names.len(), layout.columns.len());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::KRec;
+use std::collections::BTreeSet;
+use tracing::{debug, instrument};
+
+/// Options for [`KRec::tracking_error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackingOptions {
+    /// The command in frame N is compared against the state in frame N + `lag_frames`
+    pub lag_frames: usize,
+    /// Largest lag considered when estimating latency via cross-correlation
+    pub max_lag_frames: usize,
+}
+
+impl Default for TrackingOptions {
+    fn default() -> Self {
+        Self {
+            lag_frames: 0,
+            max_lag_frames: 50,
+        }
+    }
+}
+
+/// Per-frame tracking error (measured minus commanded) for one field of one actuator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackingError {
+    /// Error for each command frame; `None` where either side is missing or the actuator is offline
+    pub errors: Vec<Option<f64>>,
+    pub count: usize,
+    /// Mean error (steady-state bias)
+    pub mean: f64,
+    pub rms: f64,
+    pub max_abs: f64,
+}
+
+impl TrackingError {
+    fn from_errors(errors: Vec<Option<f64>>) -> Option<Self> {
+        let values: Vec<f64> = errors.iter().flatten().copied().collect();
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let rms = (values.iter().map(|e| e * e).sum::<f64>() / count as f64).sqrt();
+        let max_abs = values.iter().fold(0.0_f64, |acc, e| acc.max(e.abs()));
+        Some(Self {
+            errors,
+            count,
+            mean,
+            rms,
+            max_abs,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActuatorTracking {
+    pub actuator_id: u32,
+    pub name: Option<String>,
+    pub kp: Option<f64>,
+    pub kd: Option<f64>,
+    pub position: Option<TrackingError>,
+    pub velocity: Option<TrackingError>,
+    pub torque: Option<TrackingError>,
+    /// Lag (in frames) maximizing the correlation between commanded and measured position
+    pub latency_frames: Option<usize>,
+    /// `latency_frames` converted using the median frame interval
+    pub latency_ns: Option<f64>,
+}
+
+/// Per-frame samples for a single actuator, aligned by frame index.
+#[derive(Debug, Default)]
+struct Series {
+    cmd_position: Vec<Option<f64>>,
+    cmd_velocity: Vec<Option<f64>>,
+    cmd_torque: Vec<Option<f64>>,
+    position: Vec<Option<f64>>,
+    velocity: Vec<Option<f64>>,
+    torque: Vec<Option<f64>>,
+}
+
+fn lagged_errors(
+    commands: &[Option<f64>],
+    measured: &[Option<f64>],
+    lag: usize,
+) -> Vec<Option<f64>> {
+    (0..commands.len().saturating_sub(lag))
+        .map(|n| match (commands[n], measured[n + lag]) {
+            (Some(c), Some(m)) => Some(m - c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Pearson correlation between `a[n]` and `b[n + lag]` over the samples where both exist.
+fn lagged_correlation(a: &[Option<f64>], b: &[Option<f64>], lag: usize) -> Option<f64> {
+    let pairs: Vec<(f64, f64)> = (0..a.len().saturating_sub(lag))
+        .filter_map(|n| Some((a[n]?, b[n + lag]?)))
+        .collect();
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_a = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_b = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in &pairs {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return None;
+    }
+    Some(cov / (var_a * var_b).sqrt())
+}
+
+fn estimate_latency(
+    commands: &[Option<f64>],
+    measured: &[Option<f64>],
+    max_lag: usize,
+) -> Option<usize> {
+    (0..=max_lag)
+        .filter_map(|lag| lagged_correlation(commands, measured, lag).map(|r| (lag, r)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(lag, _)| lag)
+}
+
+impl KRec {
+    /// Compares each actuator's commands against its measured state.
+    ///
+    /// Errors are reported as measured minus commanded, pairing the command from frame N with
+    /// the state from frame N + `options.lag_frames`. Offline states are ignored.
+    #[instrument(skip(self))]
+    pub fn tracking_error(&self, options: &TrackingOptions) -> Vec<ActuatorTracking> {
+        let frame_count = self.frames.len();
+        let actuator_ids: BTreeSet<u32> = self
+            .frames
+            .iter()
+            .flat_map(|f| f.actuator_commands.iter().map(|c| c.actuator_id))
+            .collect();
+        debug!(
+            "Computing tracking error for {} actuators over {} frames",
+            actuator_ids.len(),
+            frame_count
+        );
+        let interval_ns = self.median_frame_interval_ns();
+
+        actuator_ids
+            .into_iter()
+            .map(|actuator_id| {
+                let mut series = Series::default();
+                for frame in &self.frames {
+                    let command = frame
+                        .actuator_commands
+                        .iter()
+                        .find(|c| c.actuator_id == actuator_id);
+                    series.cmd_position.push(command.map(|c| c.position as f64));
+                    series.cmd_velocity.push(command.map(|c| c.velocity as f64));
+                    series.cmd_torque.push(command.map(|c| c.torque as f64));
+
+                    let state = frame
+                        .actuator_states
+                        .iter()
+                        .find(|s| s.actuator_id == actuator_id && s.online);
+                    series.position.push(state.and_then(|s| s.position));
+                    series.velocity.push(state.and_then(|s| s.velocity));
+                    series.torque.push(state.and_then(|s| s.torque));
+                }
+
+                let config = self
+                    .header
+                    .actuator_configs
+                    .iter()
+                    .find(|c| c.actuator_id == actuator_id);
+                let latency_frames = estimate_latency(
+                    &series.cmd_position,
+                    &series.position,
+                    options.max_lag_frames,
+                );
+
+                ActuatorTracking {
+                    actuator_id,
+                    name: config.and_then(|c| c.name.clone()),
+                    kp: config.and_then(|c| c.kp),
+                    kd: config.and_then(|c| c.kd),
+                    position: TrackingError::from_errors(lagged_errors(
+                        &series.cmd_position,
+                        &series.position,
+                        options.lag_frames,
+                    )),
+                    velocity: TrackingError::from_errors(lagged_errors(
+                        &series.cmd_velocity,
+                        &series.velocity,
+                        options.lag_frames,
+                    )),
+                    torque: TrackingError::from_errors(lagged_errors(
+                        &series.cmd_torque,
+                        &series.torque,
+                        options.lag_frames,
+                    )),
+                    latency_frames,
+                    latency_ns: latency_frames
+                        .zip(interval_ns)
+                        .map(|(lag, interval)| lag as f64 * interval),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ActuatorCommand, ActuatorState, KRecFrame, KRecHeader};
+
+    /// One actuator (ID 1) commanded to `commands[n]` and measured at `positions[n]`.
+    fn recording(commands: &[f64], positions: &[Option<f64>], online: &[bool]) -> KRec {
+        let mut krec = KRec::new(KRecHeader::default());
+        for (i, (&command, &position)) in commands.iter().zip(positions).enumerate() {
+            krec.add_frame(KRecFrame {
+                real_timestamp: 10_000_000 * i as u64,
+                actuator_commands: vec![ActuatorCommand {
+                    actuator_id: 1,
+                    position: command as f32,
+                    ..Default::default()
+                }],
+                actuator_states: vec![ActuatorState {
+                    actuator_id: 1,
+                    online: online[i],
+                    position,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
+        krec
+    }
+
+    #[test]
+    fn error_is_measured_minus_commanded() {
+        let krec = recording(
+            &[1.0, 2.0, 3.0],
+            &[Some(1.5), Some(2.5), Some(3.5)],
+            &[true; 3],
+        );
+        let tracking = krec.tracking_error(&TrackingOptions::default());
+        let position = tracking[0].position.as_ref().unwrap();
+        assert_eq!(position.errors, vec![Some(0.5), Some(0.5), Some(0.5)]);
+        assert_eq!(position.count, 3);
+        assert_eq!(position.mean, 0.5);
+        assert_eq!(position.rms, 0.5);
+        assert_eq!(position.max_abs, 0.5);
+    }
+
+    #[test]
+    fn lag_pairs_command_with_later_state() {
+        let krec = recording(
+            &[1.0, 2.0, 3.0, 4.0],
+            &[Some(0.0), Some(1.0), Some(2.0), Some(3.0)],
+            &[true; 4],
+        );
+        let options = TrackingOptions {
+            lag_frames: 1,
+            ..Default::default()
+        };
+        let position = krec.tracking_error(&options)[0].position.clone().unwrap();
+        // Command N is paired with state N + 1, so the last command has no state
+        assert_eq!(position.errors, vec![Some(0.0), Some(0.0), Some(0.0)]);
+    }
+
+    #[test]
+    fn offline_states_are_skipped() {
+        let krec = recording(
+            &[1.0, 1.0, 1.0],
+            &[Some(2.0), Some(100.0), Some(2.0)],
+            &[true, false, true],
+        );
+        let position = krec.tracking_error(&TrackingOptions::default())[0]
+            .position
+            .clone()
+            .unwrap();
+        assert_eq!(position.errors, vec![Some(1.0), None, Some(1.0)]);
+        assert_eq!(position.count, 2);
+        assert_eq!(position.max_abs, 1.0);
+    }
+
+    #[test]
+    fn latency_is_recovered_from_delayed_signal() {
+        let delay = 3;
+        let commands: Vec<f64> = (0..60).map(|i| (i as f64 * 0.3).sin()).collect();
+        let positions: Vec<Option<f64>> = (0..60)
+            .map(|i| Some(if i < delay { 0.0 } else { commands[i - delay] }))
+            .collect();
+        let krec = recording(&commands, &positions, &[true; 60]);
+        let options = TrackingOptions {
+            max_lag_frames: 10,
+            ..Default::default()
+        };
+        let tracking = &krec.tracking_error(&options)[0];
+        assert_eq!(tracking.latency_frames, Some(delay));
+        assert_eq!(tracking.latency_ns, Some(delay as f64 * 10_000_000.0));
+    }
+}
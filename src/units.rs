@@ -0,0 +1,283 @@
+use crate::proto::proto::{AngularUnit, UnitSystem};
+use crate::proto::KRecHeader;
+use crate::KRec;
+use color_eyre::{eyre::eyre, Result};
+use std::fmt;
+use std::str::FromStr;
+use tracing::{info, instrument};
+
+/// Angular fields whose unit is declared in [`UnitSystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngularField {
+    StatePosition,
+    StateVelocity,
+    CommandPosition,
+    CommandVelocity,
+    ImuGyro,
+}
+
+impl AngularField {
+    pub const ALL: [AngularField; 5] = [
+        AngularField::StatePosition,
+        AngularField::StateVelocity,
+        AngularField::CommandPosition,
+        AngularField::CommandVelocity,
+        AngularField::ImuGyro,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AngularField::StatePosition => "state_position",
+            AngularField::StateVelocity => "state_velocity",
+            AngularField::CommandPosition => "command_position",
+            AngularField::CommandVelocity => "command_velocity",
+            AngularField::ImuGyro => "imu_gyro",
+        }
+    }
+}
+
+impl FromStr for AngularUnit {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "degrees" | "degree" | "deg" => Ok(AngularUnit::Degrees),
+            "radians" | "radian" | "rad" => Ok(AngularUnit::Radians),
+            _ => Err(eyre!(
+                "Unknown angular unit '{}', expected 'degrees' or 'radians'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AngularUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AngularUnit::Unspecified => write!(f, "unspecified"),
+            AngularUnit::Degrees => write!(f, "degrees"),
+            AngularUnit::Radians => write!(f, "radians"),
+        }
+    }
+}
+
+impl UnitSystem {
+    /// A unit system using `unit` for every angular field.
+    pub fn uniform(unit: AngularUnit) -> Self {
+        let mut units = UnitSystem::default();
+        units.set_angular(unit);
+        units
+    }
+
+    /// Per-field override for `field`, which may be unspecified.
+    fn field_unit(&self, field: AngularField) -> AngularUnit {
+        match field {
+            AngularField::StatePosition => self.state_position(),
+            AngularField::StateVelocity => self.state_velocity(),
+            AngularField::CommandPosition => self.command_position(),
+            AngularField::CommandVelocity => self.command_velocity(),
+            AngularField::ImuGyro => self.imu_gyro(),
+        }
+    }
+
+    /// Sets the per-field override for `field`.
+    pub fn set_field_unit(&mut self, field: AngularField, unit: AngularUnit) {
+        match field {
+            AngularField::StatePosition => self.set_state_position(unit),
+            AngularField::StateVelocity => self.set_state_velocity(unit),
+            AngularField::CommandPosition => self.set_command_position(unit),
+            AngularField::CommandVelocity => self.set_command_velocity(unit),
+            AngularField::ImuGyro => self.set_imu_gyro(unit),
+        }
+    }
+
+    /// Effective unit of `field`: the per-field override, then `angular`, then degrees.
+    pub fn resolve(&self, field: AngularField) -> AngularUnit {
+        [self.field_unit(field), self.angular()]
+            .into_iter()
+            .find(|unit| *unit != AngularUnit::Unspecified)
+            .unwrap_or(AngularUnit::Degrees)
+    }
+}
+
+impl KRecHeader {
+    /// Effective unit of `field`, assuming the documented defaults (degrees) for older files.
+    pub fn angular_unit(&self, field: AngularField) -> AngularUnit {
+        self.units
+            .as_ref()
+            .map(|units| units.resolve(field))
+            .unwrap_or(AngularUnit::Degrees)
+    }
+}
+
+/// Multiplier converting a value in `from` into `to`.
+fn conversion_factor(from: AngularUnit, to: AngularUnit) -> f64 {
+    match (from, to) {
+        (AngularUnit::Radians, AngularUnit::Degrees) => 180.0 / std::f64::consts::PI,
+        (AngularUnit::Degrees, AngularUnit::Radians) => std::f64::consts::PI / 180.0,
+        _ => 1.0,
+    }
+}
+
+impl KRec {
+    /// Rewrites angular states, commands and gyro values into `target` and updates the header.
+    ///
+    /// Unspecified fields in `target` resolve as described in [`UnitSystem::resolve`]. Torques
+    /// and actuator gains are left unchanged.
+    #[instrument(skip(self))]
+    pub fn convert_units(&mut self, target: &UnitSystem) {
+        let factor = |field: AngularField| {
+            conversion_factor(self.header.angular_unit(field), target.resolve(field))
+        };
+        let state_position = factor(AngularField::StatePosition);
+        let state_velocity = factor(AngularField::StateVelocity);
+        let command_position = factor(AngularField::CommandPosition);
+        let command_velocity = factor(AngularField::CommandVelocity);
+        let imu_gyro = factor(AngularField::ImuGyro);
+
+        info!(
+            "Converting {} frames to {} units",
+            self.frames.len(),
+            target.resolve(AngularField::StatePosition)
+        );
+
+        for frame in &mut self.frames {
+            for state in &mut frame.actuator_states {
+                state.position = state.position.map(|p| p * state_position);
+                state.velocity = state.velocity.map(|v| v * state_velocity);
+            }
+            for command in &mut frame.actuator_commands {
+                command.position = (command.position as f64 * command_position) as f32;
+                command.velocity = (command.velocity as f64 * command_velocity) as f32;
+            }
+            if let Some(gyro) = frame.imu_values.as_mut().and_then(|imu| imu.gyro.as_mut()) {
+                gyro.x *= imu_gyro;
+                gyro.y *= imu_gyro;
+                gyro.z *= imu_gyro;
+            }
+        }
+
+        // Record fully resolved units so the header stays accurate even if defaults change
+        let mut units = UnitSystem::uniform(target.resolve(AngularField::StatePosition));
+        for field in AngularField::ALL {
+            units.set_field_unit(field, target.resolve(field));
+        }
+        self.header.units = Some(units);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::proto::Vec3;
+    use crate::proto::{ActuatorCommand, ActuatorState, ImuValues, KRecFrame};
+    use std::f64::consts::PI;
+
+    fn sample(units: Option<UnitSystem>) -> KRec {
+        let mut krec = KRec::new(KRecHeader {
+            units,
+            ..Default::default()
+        });
+        krec.add_frame(KRecFrame {
+            actuator_states: vec![ActuatorState {
+                actuator_id: 1,
+                online: true,
+                position: Some(90.0),
+                velocity: Some(-180.0),
+                torque: Some(2.5),
+                ..Default::default()
+            }],
+            actuator_commands: vec![ActuatorCommand {
+                actuator_id: 1,
+                position: 45.0,
+                velocity: 360.0,
+                torque: 1.5,
+            }],
+            imu_values: Some(ImuValues {
+                gyro: Some(Vec3 {
+                    x: 180.0,
+                    y: 0.0,
+                    z: -90.0,
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        krec
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn scales_states_commands_and_gyro() {
+        // Older files without units are in degrees
+        let mut krec = sample(None);
+        krec.convert_units(&UnitSystem::uniform(AngularUnit::Radians));
+
+        let frame = &krec.frames[0];
+        let state = &frame.actuator_states[0];
+        assert_close(state.position.unwrap(), PI / 2.0);
+        assert_close(state.velocity.unwrap(), -PI);
+        assert_eq!(state.torque, Some(2.5));
+        let command = &frame.actuator_commands[0];
+        assert_close(command.position as f64, PI / 4.0);
+        assert_close(command.velocity as f64, 2.0 * PI);
+        assert_eq!(command.torque, 1.5);
+        let gyro = frame.imu_values.as_ref().unwrap().gyro.as_ref().unwrap();
+        assert_close(gyro.x, PI);
+        assert_close(gyro.z, -PI / 2.0);
+    }
+
+    #[test]
+    fn updates_header_units() {
+        let mut krec = sample(None);
+        let mut target = UnitSystem::uniform(AngularUnit::Radians);
+        target.set_field_unit(AngularField::ImuGyro, AngularUnit::Degrees);
+        krec.convert_units(&target);
+
+        let units = krec.header.units.as_ref().unwrap();
+        assert_eq!(units.angular(), AngularUnit::Radians);
+        for field in AngularField::ALL {
+            let expected = if field == AngularField::ImuGyro {
+                AngularUnit::Degrees
+            } else {
+                AngularUnit::Radians
+            };
+            assert_eq!(units.resolve(field), expected);
+            assert_eq!(krec.header.angular_unit(field), expected);
+        }
+        // The gyro was already in degrees, so it is untouched
+        let gyro = krec.frames[0].imu_values.as_ref().unwrap().gyro.clone();
+        assert_eq!(gyro.unwrap().x, 180.0);
+    }
+
+    #[test]
+    fn round_trip_restores_values() {
+        let original = sample(Some(UnitSystem::uniform(AngularUnit::Degrees)));
+        let mut krec = original.clone();
+        krec.convert_units(&UnitSystem::uniform(AngularUnit::Radians));
+        krec.convert_units(&UnitSystem::uniform(AngularUnit::Degrees));
+
+        let (frame, expected) = (&krec.frames[0], &original.frames[0]);
+        let (state, expected_state) = (&frame.actuator_states[0], &expected.actuator_states[0]);
+        assert_close(state.position.unwrap(), expected_state.position.unwrap());
+        assert_close(state.velocity.unwrap(), expected_state.velocity.unwrap());
+        let (command, expected_command) =
+            (&frame.actuator_commands[0], &expected.actuator_commands[0]);
+        assert_close(command.position as f64, expected_command.position as f64);
+        assert_close(command.velocity as f64, expected_command.velocity as f64);
+        for field in AngularField::ALL {
+            assert_eq!(krec.header.angular_unit(field), AngularUnit::Degrees);
+        }
+    }
+
+    #[test]
+    fn converting_to_current_units_is_a_no_op() {
+        let original = sample(Some(UnitSystem::uniform(AngularUnit::Degrees)));
+        let mut krec = original.clone();
+        krec.convert_units(&UnitSystem::uniform(AngularUnit::Degrees));
+        assert_eq!(krec.frames, original.frames);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::KRec;
+use std::collections::HashSet;
+use std::fmt;
+use tracing::{debug, instrument};
+
+/// Quaternions whose norm differs from 1 by more than this are reported.
+const QUATERNION_NORM_TOLERANCE: f64 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Severity {
+    /// Suspicious but readable data, such as missing header fields or frame gaps
+    Warning,
+    /// Data that breaks assumptions made by readers, such as decreasing timestamps
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by [`KRec::validate`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Index of the offending frame, or `None` for header issues
+    pub frame_index: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.frame_index {
+            Some(i) => write!(f, "{}: frame {}: {}", self.severity, i, self.message),
+            None => write!(f, "{}: header: {}", self.severity, self.message),
+        }
+    }
+}
+
+impl KRec {
+    /// Checks the recording for structural problems.
+    ///
+    /// Errors are duplicate actuator IDs, decreasing `real_timestamp`s and non-finite actuator
+    /// values. Warnings are empty header fields, header timestamps that do not cover the
+    /// frames, data for actuators without a config, decreasing video frame numbers,
+    /// non-normalized IMU quaternions and gaps between frames (see [`KRec::stats`]).
+    #[instrument(skip(self))]
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |severity, frame_index, message: String| {
+            issues.push(ValidationIssue {
+                severity,
+                frame_index,
+                message,
+            })
+        };
+
+        let header = &self.header;
+        for (name, value) in [
+            ("uuid", &header.uuid),
+            ("task", &header.task),
+            ("robot_platform", &header.robot_platform),
+            ("robot_serial", &header.robot_serial),
+        ] {
+            if value.is_empty() {
+                issue(Severity::Warning, None, format!("{} is empty", name));
+            }
+        }
+
+        let mut config_ids = HashSet::new();
+        for config in &header.actuator_configs {
+            if !config_ids.insert(config.actuator_id) {
+                issue(
+                    Severity::Error,
+                    None,
+                    format!("duplicate config for actuator {}", config.actuator_id),
+                );
+            }
+        }
+
+        if let (Some(first), Some(last)) = (self.frames.first(), self.frames.last()) {
+            if header.start_timestamp != 0 && header.start_timestamp > first.real_timestamp {
+                issue(
+                    Severity::Warning,
+                    None,
+                    format!(
+                        "start_timestamp {} is after the first frame ({})",
+                        header.start_timestamp, first.real_timestamp
+                    ),
+                );
+            }
+            if header.end_timestamp != 0 && header.end_timestamp < last.real_timestamp {
+                issue(
+                    Severity::Warning,
+                    None,
+                    format!(
+                        "end_timestamp {} is before the last frame ({})",
+                        header.end_timestamp, last.real_timestamp
+                    ),
+                );
+            }
+        }
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            let frame_index = Some(i);
+            if let Some(previous) = i.checked_sub(1).map(|p| &self.frames[p]) {
+                if frame.real_timestamp < previous.real_timestamp {
+                    issue(
+                        Severity::Error,
+                        frame_index,
+                        format!(
+                            "real_timestamp {} is before the previous frame ({})",
+                            frame.real_timestamp, previous.real_timestamp
+                        ),
+                    );
+                }
+                if frame.video_frame_number < previous.video_frame_number {
+                    issue(
+                        Severity::Warning,
+                        frame_index,
+                        format!(
+                            "video_frame_number {} is before the previous frame ({})",
+                            frame.video_frame_number, previous.video_frame_number
+                        ),
+                    );
+                }
+            }
+
+            let mut state_ids = HashSet::new();
+            for state in &frame.actuator_states {
+                let id = state.actuator_id;
+                if !state_ids.insert(id) {
+                    issue(
+                        Severity::Error,
+                        frame_index,
+                        format!("duplicate state for actuator {}", id),
+                    );
+                }
+                if !config_ids.is_empty() && !config_ids.contains(&id) {
+                    issue(
+                        Severity::Warning,
+                        frame_index,
+                        format!("state for actuator {} without a config", id),
+                    );
+                }
+                for (name, value) in [
+                    ("position", state.position),
+                    ("velocity", state.velocity),
+                    ("torque", state.torque),
+                ] {
+                    if value.is_some_and(|v| !v.is_finite()) {
+                        issue(
+                            Severity::Error,
+                            frame_index,
+                            format!("actuator {} {} is not finite", id, name),
+                        );
+                    }
+                }
+            }
+
+            let mut command_ids = HashSet::new();
+            for command in &frame.actuator_commands {
+                let id = command.actuator_id;
+                if !command_ids.insert(id) {
+                    issue(
+                        Severity::Error,
+                        frame_index,
+                        format!("duplicate command for actuator {}", id),
+                    );
+                }
+                if !config_ids.is_empty() && !config_ids.contains(&id) {
+                    issue(
+                        Severity::Warning,
+                        frame_index,
+                        format!("command for actuator {} without a config", id),
+                    );
+                }
+                if [command.position, command.velocity, command.torque]
+                    .iter()
+                    .any(|v| !v.is_finite())
+                {
+                    issue(
+                        Severity::Error,
+                        frame_index,
+                        format!("actuator {} command is not finite", id),
+                    );
+                }
+            }
+
+            if let Some(q) = frame
+                .imu_values
+                .as_ref()
+                .and_then(|imu| imu.quaternion.as_ref())
+            {
+                let norm = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+                if (norm - 1.0).abs() > QUATERNION_NORM_TOLERANCE {
+                    issue(
+                        Severity::Warning,
+                        frame_index,
+                        format!("IMU quaternion has norm {:.4}", norm),
+                    );
+                }
+            }
+        }
+
+        for gap in self.stats().gaps {
+            issue(
+                Severity::Warning,
+                Some(gap.frame_index + 1),
+                format!(
+                    "gap of {:.3} ms after the previous frame (~{} dropped frames)",
+                    gap.interval_ns as f64 / 1e6,
+                    gap.dropped_frames
+                ),
+            );
+        }
+
+        debug!("Found {} validation issues", issues.len());
+        issues
+    }
+}